@@ -1,61 +1,496 @@
 use std::path::PathBuf;
 use std::str::FromStr;
 
-use clap::{ArgGroup, Parser};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use spreet::resvg::usvg;
+use spreet::{
+    ImageFormat, IndexFormat, InputFilter, LoadOptions, Orientation, PngFilter, PngOptions,
+    RenderOptions, TargetSize,
+};
 
 /// Container for Spreet's command-line arguments.
 #[derive(Parser)]
 #[command(version, about)]
-#[command(group(ArgGroup::new("pixel_ratio").args(&["ratio", "retina"])))]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Cli {
-    /// A directory of SVGs to include in the spritesheet
-    #[arg(value_parser = is_dir)]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub build: Option<BuildArgs>,
+}
+
+/// Commands other than building a spritesheet.
+#[derive(Subcommand)]
+pub enum Command {
+    /// Inspect or prune a sprite cache
+    #[command(subcommand)]
+    Cache(CacheCommand),
+    /// Check SVGs for problems that make them poor sources for sprites
+    Lint(LintArgs),
+}
+
+/// Arguments used to lint SVGs.
+#[derive(Args)]
+pub struct LintArgs {
+    /// An SVG file, or a directory of SVGs, to check
+    pub input: PathBuf,
+    /// Include images in sub-directories
+    #[arg(long)]
+    pub recursive: bool,
+    /// Report images whose width or height isn't a multiple of this many pixels
+    #[arg(long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub grid: Option<u32>,
+    /// Report images wider or taller than this many pixels
+    #[arg(long, value_name = "PIXELS", default_value_t = 512.0, value_parser = is_positive)]
+    pub max_size: f32,
+    /// Render text with a font file, or the fonts in a directory (can be repeated)
+    #[arg(long = "font", value_name = "PATH", value_parser = is_existing_path)]
+    pub fonts: Vec<PathBuf>,
+    /// Don't render text with fonts installed on the system
+    #[arg(long)]
+    pub no_system_fonts: bool,
+    /// Font family for text without a `font-family`, and for generic and unavailable families
+    #[arg(long, value_name = "NAME")]
+    pub font_family: Option<String>,
+}
+
+/// Commands to manage a sprite cache.
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Show the number and total size of cached sprites
+    Info {
+        /// The cache directory
+        dir: PathBuf,
+    },
+    /// Remove cached sprites
+    Prune {
+        /// The cache directory
+        dir: PathBuf,
+        /// Only remove sprites that haven't been used for this many days
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+    },
+}
+
+/// Arguments used to build a spritesheet.
+#[derive(Args)]
+#[command(group(ArgGroup::new("pixel_ratio").args(&["ratio", "retina"])))]
+pub struct BuildArgs {
+    /// A directory of SVGs, or an archive (.zip, .tar or .tar.gz) of SVGs, to include in the
+    /// spritesheet
+    #[arg(value_parser = is_dir_or_file)]
     pub input: PathBuf,
     /// Name of the file in which to save the spritesheet
     pub output: String,
     /// Set the output pixel ratio
-    #[arg(short, long, default_value_t = 1, value_parser = is_positive)]
-    pub ratio: u8,
+    #[arg(short, long, default_value_t = 1.0, value_parser = is_positive)]
+    pub ratio: f32,
     /// Set the pixel ratio to 2 (equivalent to `--ratio=2`)
     #[arg(long)]
     pub retina: bool,
     /// Store only unique images in the spritesheet, and map them to multiple names
     #[arg(long)]
     pub unique: bool,
+    /// Also merge images whose difference is within a threshold (requires `--unique`)
+    #[arg(long, value_name = "MAX_ERROR", requires = "unique", value_parser = is_non_negative_float)]
+    pub unique_tolerance: Option<f32>,
+    /// Print the names of images that share one sprite (requires `--unique`)
+    #[arg(long, requires = "unique")]
+    pub print_duplicates: bool,
+    /// Save the names of images that share one sprite as JSON (requires `--unique`)
+    #[arg(long, value_name = "FILE", requires = "unique")]
+    pub duplicates_file: Option<PathBuf>,
     /// Include images in sub-directories
     #[arg(long)]
     pub recursive: bool,
+    /// Only include the icons listed in a file (names or paths, one per line or as a JSON array),
+    /// or on stdin if `-`
+    #[arg(long, value_name = "FILE")]
+    pub manifest: Option<PathBuf>,
+    /// Only include the icons used by a MapLibre/Mapbox style JSON file
+    #[arg(long, value_name = "FILE")]
+    pub style: Option<PathBuf>,
+    /// With `--style`, also include icons whose names match a glob (can be repeated)
+    #[arg(long = "allow-icon", value_name = "GLOB", requires = "style")]
+    pub allow_icons: Vec<String>,
+    /// Only include SVGs in the input that match a glob (can be repeated)
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Leave out SVGs and sub-directories that match a glob (can be repeated)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    /// Also generate sprites from the SVG templates listed in a JSON file
+    #[arg(long, value_name = "FILE")]
+    pub templates: Option<PathBuf>,
+    /// Also generate sprites by layering the SVGs listed in a JSON file
+    #[arg(long, value_name = "FILE")]
+    pub compositions: Option<PathBuf>,
+    /// Leave out SVGs that can't be loaded, instead of failing
+    #[arg(long)]
+    pub skip_invalid: bool,
     /// Add pixel spacing between sprites
     #[arg(long, default_value_t = 0, value_parser = is_non_negative)]
     pub spacing: u8,
-    /// Remove whitespace from the JSON index file
+    /// Format of the index file, for map styles or other renderers
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = Index::Maplibre)]
+    pub index_format: Index,
+    /// Remove whitespace from the index file
     #[arg(short, long)]
     pub minify_index_file: bool,
+    /// Also save a CSS stylesheet with a class for each sprite, for using the icons in web pages
+    #[arg(long)]
+    pub css: bool,
+    /// Prefix added to each sprite name to make its CSS class name
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        default_value = "sprite-",
+        requires = "css"
+    )]
+    pub css_prefix: String,
+    /// With `--css`, also save the spritesheet at these pixel ratios (e.g. `2,3`), for browsers to
+    /// choose from
+    #[arg(
+        long,
+        value_name = "RATIOS",
+        value_delimiter = ',',
+        value_parser = is_positive,
+        requires = "css"
+    )]
+    pub css_ratios: Vec<f32>,
     /// Output a spritesheet using a signed distance field for each sprite
     #[arg(long)]
     pub sdf: bool,
+    /// Image format of the spritesheet
+    #[arg(long, value_enum, default_value_t = Format::Png)]
+    pub format: Format,
+    /// Quality of lossy image formats, from 1 to 100
+    #[arg(long, default_value_t = 80.0, value_parser = is_quality)]
+    pub quality: f32,
+    /// How much effort to spend optimising PNG spritesheets
+    #[arg(long, value_name = "PRESET", value_enum, default_value_t = PngPreset::Default)]
+    pub png_preset: PngPreset,
+    /// Set the PNG optimisation level (0-6), overriding the preset
+    #[arg(long, value_name = "LEVEL", value_parser = clap::value_parser!(u8).range(0..=6))]
+    pub png_level: Option<u8>,
+    /// Set the PNG row filters to try, overriding the preset
+    #[arg(long, value_name = "FILTERS", value_enum, value_delimiter = ',')]
+    pub png_filters: Vec<Filter>,
+    /// Compress PNG spritesheets with Zopfli (slow)
+    #[arg(long)]
+    pub zopfli: bool,
+    /// Remove metadata from PNG spritesheets
+    #[arg(long)]
+    pub strip_metadata: bool,
+    /// Reduce the spritesheet to 256 colours if the error is within a threshold
+    #[arg(
+        long,
+        value_name = "MAX_ERROR",
+        num_args = 0..=1,
+        default_missing_value = "2",
+        value_parser = is_non_negative_float
+    )]
+    pub quantize: Option<f32>,
+    /// Render sprites at a fixed size (e.g. `height=24`, `width=24` or `box=24x24,square`)
+    #[arg(long, value_name = "SIZE", value_parser = parse_render_options)]
+    pub size: Option<RenderOptions>,
+    /// Override `--size` for a sub-directory (e.g. `shields=height=18`)
+    #[arg(long, value_name = "DIR=SIZE", value_parser = parse_dir_render_options)]
+    pub dir_size: Vec<(PathBuf, RenderOptions)>,
+    /// Also render rotated or mirrored variants of a sprite or directory (e.g. `arrows=90,mirror`)
+    #[arg(long, value_name = "NAME=LIST", value_parser = parse_variants)]
+    pub variants: Vec<(PathBuf, Vec<Orientation>)>,
+    /// Align the SVG's grid to whole pixels to keep edges sharp
+    #[arg(long)]
+    pub snap_to_pixels: bool,
+    /// Default `shape-rendering` (antialiasing) mode for shapes
+    #[arg(long, value_name = "MODE", value_enum, default_value_t = ShapeRendering::GeometricPrecision)]
+    pub shape_rendering: ShapeRendering,
+    /// Render text with a font file, or the fonts in a directory (can be repeated)
+    #[arg(long = "font", value_name = "PATH", value_parser = is_existing_path)]
+    pub fonts: Vec<PathBuf>,
+    /// Don't render text with fonts installed on the system
+    #[arg(long)]
+    pub no_system_fonts: bool,
+    /// Font family for text without a `font-family`, and for generic and unavailable families
+    #[arg(long, value_name = "NAME")]
+    pub font_family: Option<String>,
+    /// Number of threads used to render sprites (default: one per CPU core)
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+    /// Reuse sprites rendered by earlier builds from a cache directory
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+    /// Save a JSON report about the build, and print a summary
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+}
+
+/// Image formats for the spritesheet.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Png,
+    #[cfg(feature = "webp")]
+    Webp,
+    #[cfg(feature = "webp")]
+    WebpLossy,
+    #[cfg(feature = "avif")]
+    Avif,
+}
+
+impl Format {
+    /// Returns the library's image format, using `quality` for lossy formats.
+    #[cfg_attr(not(any(feature = "webp", feature = "avif")), allow(unused_variables))]
+    pub fn image_format(self, quality: f32) -> ImageFormat {
+        match self {
+            Format::Png => ImageFormat::Png,
+            #[cfg(feature = "webp")]
+            Format::Webp => ImageFormat::LosslessWebp,
+            #[cfg(feature = "webp")]
+            Format::WebpLossy => ImageFormat::Webp { quality },
+            #[cfg(feature = "avif")]
+            Format::Avif => ImageFormat::Avif { quality },
+        }
+    }
+}
+
+/// Formats of the index file.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Index {
+    Maplibre,
+    Deckgl,
+    TexturePackerHash,
+    TexturePackerArray,
+    Xml,
+}
+
+impl Index {
+    /// Returns the library's index format.
+    pub fn index_format(self) -> IndexFormat {
+        match self {
+            Index::Maplibre => IndexFormat::MapLibre,
+            Index::Deckgl => IndexFormat::DeckGl,
+            Index::TexturePackerHash => IndexFormat::TexturePackerHash,
+            Index::TexturePackerArray => IndexFormat::TexturePackerArray,
+            Index::Xml => IndexFormat::Xml,
+        }
+    }
+}
+
+/// Presets for PNG optimisation.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PngPreset {
+    Fast,
+    Default,
+    Max,
+}
+
+/// Row filters tried when optimising a PNG.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Filter {
+    None,
+    Sub,
+    Up,
+    Average,
+    Paeth,
+    MinSum,
+    Entropy,
+    Bigrams,
+    BigEnt,
+    Brute,
+}
+
+impl From<Filter> for PngFilter {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::None => PngFilter::None,
+            Filter::Sub => PngFilter::Sub,
+            Filter::Up => PngFilter::Up,
+            Filter::Average => PngFilter::Average,
+            Filter::Paeth => PngFilter::Paeth,
+            Filter::MinSum => PngFilter::MinSum,
+            Filter::Entropy => PngFilter::Entropy,
+            Filter::Bigrams => PngFilter::Bigrams,
+            Filter::BigEnt => PngFilter::BigEnt,
+            Filter::Brute => PngFilter::Brute,
+        }
+    }
 }
 
-/// Clap validator to ensure that a string is an existing directory.
-fn is_dir(p: &str) -> Result<PathBuf, String> {
-    if PathBuf::from(p).is_dir() {
+impl LintArgs {
+    /// Returns the options used to parse SVGs.
+    pub fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            fonts: self.fonts.clone(),
+            system_fonts: !self.no_system_fonts,
+            font_family: self.font_family.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl BuildArgs {
+    /// Returns the filter that chooses which SVGs in the input directory are used.
+    pub fn input_filter(&self) -> InputFilter {
+        InputFilter {
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the options used to parse SVGs.
+    pub fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            shape_rendering: self.shape_rendering.into(),
+            fonts: self.fonts.clone(),
+            system_fonts: !self.no_system_fonts,
+            font_family: self.font_family.clone(),
+        }
+    }
+
+    /// Returns the PNG options given by the preset and any options that override it.
+    pub fn png_options(&self) -> PngOptions {
+        let mut options = match self.png_preset {
+            PngPreset::Fast => PngOptions::fast(),
+            PngPreset::Default => PngOptions::default(),
+            PngPreset::Max => PngOptions::max(),
+        };
+        if self.png_level.is_some() {
+            options.level = self.png_level;
+        }
+        if !self.png_filters.is_empty() {
+            options.filters = self.png_filters.iter().map(|f| (*f).into()).collect();
+        }
+        options.zopfli |= self.zopfli;
+        options.strip_metadata |= self.strip_metadata;
+        options
+    }
+}
+
+/// Antialiasing modes, named after the values of the SVG `shape-rendering` property.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ShapeRendering {
+    OptimizeSpeed,
+    CrispEdges,
+    GeometricPrecision,
+}
+
+impl From<ShapeRendering> for usvg::ShapeRendering {
+    fn from(mode: ShapeRendering) -> Self {
+        match mode {
+            ShapeRendering::OptimizeSpeed => Self::OptimizeSpeed,
+            ShapeRendering::CrispEdges => Self::CrispEdges,
+            ShapeRendering::GeometricPrecision => Self::GeometricPrecision,
+        }
+    }
+}
+
+/// Clap validator to ensure that a string is an existing directory or file.
+fn is_dir_or_file(p: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(p);
+    if path.is_dir() || path.is_file() {
+        Ok(path)
+    } else {
+        Err(String::from("must be an existing directory or archive"))
+    }
+}
+
+/// Clap validator to ensure that a string is an existing file or directory.
+fn is_existing_path(p: &str) -> Result<PathBuf, String> {
+    if PathBuf::from(p).exists() {
         Ok(p.into())
     } else {
-        Err(String::from("must be an existing directory"))
+        Err(String::from("must be an existing file or directory"))
     }
 }
 
-/// Clap validator to ensure that an unsigned integer parsed from a string is greater than zero.
-fn is_positive(s: &str) -> Result<u8, String> {
-    u8::from_str(s)
+/// Clap validator to ensure that a number parsed from a string is finite and greater than zero.
+fn is_positive(s: &str) -> Result<f32, String> {
+    f32::from_str(s)
         .map_err(|e| e.to_string())
         .and_then(|result| match result {
-            i if i > 0 => Ok(result),
+            i if i.is_finite() && i > 0.0 => Ok(result),
             _ => Err(String::from("must be greater than zero")),
         })
 }
 
+/// Clap validator to ensure that a number parsed from a string is finite and non-negative.
+fn is_non_negative_float(s: &str) -> Result<f32, String> {
+    f32::from_str(s)
+        .ok()
+        .filter(|result| result.is_finite() && *result >= 0.0)
+        .ok_or_else(|| String::from("must be a non-negative number"))
+}
+
+/// Clap validator to ensure that an image quality parsed from a string is between 1 and 100.
+fn is_quality(s: &str) -> Result<f32, String> {
+    f32::from_str(s)
+        .ok()
+        .filter(|result| (1.0..=100.0).contains(result))
+        .ok_or_else(|| String::from("must be a number from 1 to 100"))
+}
+
 /// Clap validator to ensure that an unsigned integer parsed from a string is non-negative.
 fn is_non_negative(s: &str) -> Result<u8, String> {
     u8::from_str(s).map_err(|_| String::from("must be a non-negative number"))
 }
+
+/// Clap validator to parse render options from a comma-separated list of `height=N`, `width=N`,
+/// `box=WxH` and `square`.
+fn parse_render_options(s: &str) -> Result<RenderOptions, String> {
+    let mut options = RenderOptions::default();
+    for part in s.split(',') {
+        let size = match part.trim().split_once('=') {
+            Some(("height", value)) => TargetSize::Height(parse_dimension(value)?),
+            Some(("width", value)) => TargetSize::Width(parse_dimension(value)?),
+            Some(("box", value)) => {
+                let Some((width, height)) = value.split_once('x') else {
+                    return Err(String::from("box must be given as WIDTHxHEIGHT"));
+                };
+                TargetSize::Box {
+                    width: parse_dimension(width)?,
+                    height: parse_dimension(height)?,
+                }
+            }
+            None if part.trim() == "square" => {
+                options.square = true;
+                continue;
+            }
+            _ => return Err(format!("unknown size option \"{part}\"")),
+        };
+        options.size = size;
+    }
+    Ok(options)
+}
+
+/// Clap validator to parse a sub-directory and its render options, separated by `=`.
+fn parse_dir_render_options(s: &str) -> Result<(PathBuf, RenderOptions), String> {
+    let Some((dir, options)) = s.split_once('=') else {
+        return Err(String::from("must be given as DIR=SIZE"));
+    };
+    Ok((PathBuf::from(dir), parse_render_options(options)?))
+}
+
+/// Clap validator to parse a sprite name or sub-directory and a comma-separated list of
+/// orientations, separated by `=`.
+fn parse_variants(s: &str) -> Result<(PathBuf, Vec<Orientation>), String> {
+    let Some((name, orientations)) = s.split_once('=') else {
+        return Err(String::from("must be given as NAME=LIST"));
+    };
+    let orientations = orientations
+        .split(',')
+        .map(Orientation::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((PathBuf::from(name), orientations))
+}
+
+/// Parse a width or height in pixels, which must be greater than zero.
+fn parse_dimension(s: &str) -> Result<u32, String> {
+    u32::from_str(s.trim())
+        .map_err(|e| e.to_string())
+        .and_then(|result| match result {
+            i if i > 0 => Ok(result),
+            _ => Err(String::from("size must be greater than zero")),
+        })
+}
@@ -1,78 +1,615 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use clap::Parser;
-use spreet::{get_svg_input_paths, load_svg, sprite_name, Sprite, Spritesheet};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+#[cfg(any(feature = "zip", feature = "tar"))]
+use spreet::ArchiveSource;
+use spreet::{
+    get_svg_input_paths, get_svg_input_paths_with_filter, lint_svg, load_compositions,
+    load_template_sets, parse_manifest, resolve_manifest, sprite_name, spritesheet_css, CssImage,
+    CssOptions, ImageFormat, LintOptions, LoadError, PngReport, RenderOptions, SpreetError, Sprite,
+    SpriteCache, SpriteLoader, SpriteSource, Spritesheet, StyleIcons,
+};
 
 mod cli;
+mod report;
 
 fn main() {
-    let args = cli::Cli::parse();
+    let cli = cli::Cli::parse();
+    match cli.command {
+        Some(cli::Command::Cache(command)) => cache(command),
+        Some(cli::Command::Lint(args)) => lint(args),
+        // Without a subcommand, clap requires the build arguments.
+        None => build(cli.build.expect("build arguments are required")),
+    }
+}
 
+/// Build a spritesheet and its index file.
+fn build(args: cli::BuildArgs) {
+    if args.quantize.is_some() && args.format.image_format(args.quality) != ImageFormat::Png {
+        cli::Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--quantize can only be used with PNG spritesheets (--format png)",
+            )
+            .exit();
+    }
     // The ratio between the pixels in an SVG image and the pixels in the resulting PNG sprite. A
     // value of 2 means the PNGs will be double the size of the SVG images.
-    let pixel_ratio = if args.retina { 2 } else { args.ratio };
+    let pixel_ratio = if args.retina { 2.0 } else { args.ratio };
+    let mut timer = report::Timer::start();
+    let mut timings = report::Timings::default();
 
     // Collect the file paths for all SVG images in the input directory.
     // Read from all the input SVG files, convert them into bitmaps at the correct pixel ratio, and
     // store them in a map. The keys are the SVG filenames without the `.svg` extension. The
     // bitmapped SVGs will be added to the spritesheet, and the keys will be used as the unique
     // sprite ids in the JSON index file.
-    let Ok(input_paths) = get_svg_input_paths(&args.input, args.recursive) else {
-        eprintln!("Error: no valid SVGs found in {:?}", args.input);
-        std::process::exit(exitcode::NOINPUT);
-    };
-    let sprites = input_paths
-        .iter()
-        .map(|svg_path| {
-            if let Ok(tree) = load_svg(svg_path) {
-                let sprite = if args.sdf {
-                    Sprite::new_sdf(tree, pixel_ratio).expect("failed to load an SDF sprite")
-                } else {
-                    Sprite::new(tree, pixel_ratio).expect("failed to load a sprite")
-                };
-                if let Ok(name) = sprite_name(svg_path, args.input.as_path()) {
-                    (name, sprite)
-                } else {
-                    eprintln!("Error: cannot make a valid sprite name from {svg_path:?}");
-                    std::process::exit(exitcode::DATAERR);
-                }
-            } else {
-                eprintln!("{svg_path:?}: not a valid SVG image");
-                std::process::exit(exitcode::DATAERR);
+    // The input can also be an archive of SVGs, in which case the paths are relative to the
+    // archive's root, or only the icons listed in a manifest can be used.
+    let archive = (!args.input.is_dir()).then(|| open_archive(&args.input));
+    let mut input_paths = if let Some(manifest) = &args.manifest {
+        if archive.is_some() {
+            eprintln!("Error: --manifest can only be used with an input directory");
+            std::process::exit(exitcode::USAGE);
+        }
+        manifest_paths(manifest, &args.input)
+    } else {
+        let input_paths = match &archive {
+            Some(archive) => archive.svg_paths_with_filter(args.recursive, &args.input_filter()),
+            None => {
+                get_svg_input_paths_with_filter(&args.input, args.recursive, &args.input_filter())
             }
-        })
-        .collect::<BTreeMap<String, Sprite>>();
+        };
+        match input_paths {
+            Ok(input_paths) => input_paths,
+            Err(e @ SpreetError::PatternError(_)) => {
+                eprintln!("Error: {e}");
+                std::process::exit(exitcode::USAGE);
+            }
+            Err(_) => {
+                eprintln!("Error: no valid SVGs found in {:?}", args.input);
+                std::process::exit(exitcode::NOINPUT);
+            }
+        }
+    };
+    // Leave out the icons a style doesn't use. Variants are named after their icons, so icons can
+    // only be left out before they're rendered if there are no variants.
+    let style_icons = args
+        .style
+        .as_ref()
+        .map(|path| style_icons(path, &args.allow_icons));
+    if let (Some(icons), true) = (&style_icons, args.variants.is_empty()) {
+        let base_path = match archive {
+            Some(_) => Path::new("."),
+            None => args.input.as_path(),
+        };
+        input_paths
+            .retain(|path| sprite_name(path, base_path).is_ok_and(|name| icons.contains(&name)));
+    }
+    timings.discover = timer.lap();
+    let loader = sprite_loader(&args, pixel_ratio);
+    let mut reported = BTreeSet::new();
+    let sprites = load_sprites(
+        &args,
+        &loader,
+        archive.as_deref(),
+        &input_paths,
+        style_icons.as_ref(),
+        &mut reported,
+    );
+
+    if let Some(icons) = &style_icons {
+        for name in icons
+            .names()
+            .iter()
+            .filter(|name| !sprites.contains_key(*name))
+        {
+            eprintln!("Warning: the style uses the icon {name:?}, which isn't in the spritesheet");
+        }
+    }
+
+    timings.render = timer.lap();
 
     if sprites.is_empty() {
         eprintln!("Error: no valid SVGs found in {:?}", args.input);
         std::process::exit(exitcode::NOINPUT);
     }
 
+    let spritesheet = generate_spritesheet(&args, sprites);
+    timings.pack = timer.lap();
+
+    for near_duplicate in spritesheet.near_duplicates() {
+        eprintln!(
+            "Warning: {:?} is nearly identical to {:?} (error {:.2}) and has been merged with it",
+            near_duplicate.name, near_duplicate.duplicate_of, near_duplicate.error,
+        );
+    }
+
+    if args.print_duplicates {
+        for group in spritesheet.duplicate_groups() {
+            println!("{}", group.join(", "));
+        }
+    }
+    if let Some(path) = &args.duplicates_file {
+        let json = serde_json::to_string_pretty(spritesheet.duplicate_groups())
+            .expect("duplicate groups are valid JSON");
+        if let Err(e) = std::fs::write(path, json) {
+            eprintln!("Error: could not save duplicates to {path:?} ({e})");
+            std::process::exit(exitcode::IOERR);
+        };
+    }
+
+    // Save the bitmapped spritesheet and its index to local files.
+    let file_prefix = &args.output;
+    let saved = save_spritesheet(&args, &spritesheet, file_prefix, &mut timer, &mut timings);
+    if args.css {
+        // Render the spritesheet again at each of the other pixel ratios, so that the stylesheet
+        // can let browsers choose the one that suits the screen.
+        let mut ratios = args.css_ratios.clone();
+        ratios.sort_by(f32::total_cmp);
+        ratios.dedup();
+        ratios.retain(|&ratio| ratio != pixel_ratio);
+        let mut other_spritesheets = Vec::new();
+        for ratio in ratios {
+            let loader = sprite_loader(&args, ratio);
+            let sprites = load_sprites(
+                &args,
+                &loader,
+                archive.as_deref(),
+                &input_paths,
+                style_icons.as_ref(),
+                &mut reported,
+            );
+            timings.render += timer.lap();
+            let spritesheet = generate_spritesheet(&args, sprites);
+            timings.pack += timer.lap();
+            let prefix = ratio_file_prefix(file_prefix, ratio);
+            let saved = save_spritesheet(&args, &spritesheet, &prefix, &mut timer, &mut timings);
+            other_spritesheets.push((saved.path, spritesheet));
+        }
+        // The stylesheet refers to the spritesheets relative to itself, in the same directory.
+        let images = std::iter::once((&saved.path, &spritesheet))
+            .chain(
+                other_spritesheets
+                    .iter()
+                    .map(|(path, spritesheet)| (path, spritesheet)),
+            )
+            .map(|(path, spritesheet)| CssImage {
+                spritesheet,
+                url: Path::new(path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        let options = CssOptions {
+            class_prefix: args.css_prefix.clone(),
+        };
+        let css_path = format!("{file_prefix}.css");
+        if let Err(e) = std::fs::write(&css_path, spritesheet_css(&images, &options)) {
+            eprintln!("Error: could not save stylesheet to {css_path} ({e})");
+            std::process::exit(exitcode::IOERR);
+        };
+    }
+    timings.save += timer.lap();
+
+    if let Some(report_path) = &args.report {
+        let report =
+            report::Report::new(&spritesheet, saved.size, saved.png_report.as_ref(), timings);
+        eprintln!("{}", report.summary());
+        if let Err(e) = report.save(report_path) {
+            eprintln!("Error: could not save report to {report_path:?} ({e})");
+            std::process::exit(exitcode::IOERR);
+        };
+    }
+}
+
+/// Returns a sprite loader that renders sprites at `pixel_ratio` with the options in `args`.
+fn sprite_loader(args: &cli::BuildArgs, pixel_ratio: f32) -> SpriteLoader {
+    let mut loader = SpriteLoader::new(pixel_ratio)
+        .load_options(args.load_options())
+        .render_options(RenderOptions {
+            snap_to_pixels: args.snap_to_pixels,
+            ..args.size.unwrap_or_default()
+        });
+    for (dir, options) in &args.dir_size {
+        loader = loader.dir_render_options(
+            dir,
+            RenderOptions {
+                snap_to_pixels: args.snap_to_pixels,
+                ..*options
+            },
+        );
+    }
+    for (name, orientations) in &args.variants {
+        loader = loader.variants(name, orientations.clone());
+    }
+    if args.sdf {
+        loader = loader.make_sdf();
+    }
+    if let Some(jobs) = args.jobs {
+        loader = loader.jobs(jobs.into());
+    }
+    if let Some(cache_dir) = &args.cache_dir {
+        match SpriteCache::new(cache_dir) {
+            Ok(cache) => loader = loader.cache(cache),
+            Err(e) => {
+                eprintln!("Error: could not open cache directory {cache_dir:?} ({e})");
+                std::process::exit(exitcode::CANTCREAT);
+            }
+        }
+    }
+    loader
+}
+
+/// Render the sprites at `input_paths` (in `archive`, or in the input directory), and any
+/// templates and compositions, with `loader`. Leaves out the sprites that aren't in
+/// `style_icons`. Reports every sprite that couldn't be rendered, and exits unless
+/// `--skip-invalid` is given. Files in `reported` have already been reported (when rendering at
+/// another pixel ratio), so they aren't reported again, and newly reported files are added to it.
+fn load_sprites(
+    args: &cli::BuildArgs,
+    loader: &SpriteLoader,
+    archive: Option<&dyn SpriteSource>,
+    input_paths: &[PathBuf],
+    style_icons: Option<&StyleIcons>,
+    reported: &mut BTreeSet<PathBuf>,
+) -> BTreeMap<String, Sprite> {
+    let mut results = match archive {
+        Some(archive) => loader.load_source(archive, input_paths),
+        None => loader.load(input_paths, &args.input),
+    };
+    if let Some(path) = &args.templates {
+        match load_template_sets(path) {
+            Ok(sets) => results.extend(loader.load_templates(&sets)),
+            Err(e) => {
+                eprintln!("Error: could not read templates from {path:?} ({e})");
+                std::process::exit(exitcode::DATAERR);
+            }
+        }
+    }
+    if let Some(path) = &args.compositions {
+        match load_compositions(path) {
+            Ok(compositions) => results.extend(loader.load_compositions(&compositions)),
+            Err(e) => {
+                eprintln!("Error: could not read compositions from {path:?} ({e})");
+                std::process::exit(exitcode::DATAERR);
+            }
+        }
+    }
+    if let Some(icons) = style_icons {
+        results.retain(|result| {
+            result
+                .as_ref()
+                .map_or(true, |(name, _)| icons.contains(name))
+        });
+    }
+    let total = results.len();
+    let mut sprites = BTreeMap::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok((name, sprite)) => {
+                sprites.insert(name, sprite);
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+    errors.retain(|error| reported.insert(error.path.clone()));
+    // Report every file that couldn't be loaded, not just the first.
+    let level = if args.skip_invalid {
+        "Warning"
+    } else {
+        "Error"
+    };
+    for error in &errors {
+        eprintln!("{level}: {}", describe_load_error(error));
+    }
+    if !errors.is_empty() && !args.skip_invalid {
+        eprintln!(
+            "Error: {} of {} SVGs could not be loaded (use --skip-invalid to leave them out)",
+            errors.len(),
+            total,
+        );
+        std::process::exit(exitcode::DATAERR);
+    }
+    sprites
+}
+
+/// Pack `sprites` into a spritesheet with the options in `args`.
+fn generate_spritesheet(args: &cli::BuildArgs, sprites: BTreeMap<String, Sprite>) -> Spritesheet {
     let mut spritesheet_builder = Spritesheet::build().sprites(sprites).spacing(args.spacing);
-    if args.unique {
+    if let Some(max_error) = args.unique_tolerance {
+        spritesheet_builder = spritesheet_builder.make_unique_with_tolerance(max_error);
+    } else if args.unique {
         spritesheet_builder = spritesheet_builder.make_unique();
     }
     if args.sdf {
         spritesheet_builder = spritesheet_builder.make_sdf();
     }
+    if let Some(max_error) = args.quantize {
+        spritesheet_builder = spritesheet_builder.quantize(max_error);
+    }
+    spritesheet_builder = spritesheet_builder
+        .format(args.format.image_format(args.quality))
+        .png_options(args.png_options());
 
     // Generate sprite sheet
-    let Some(spritesheet) = spritesheet_builder.generate() else {
-        eprintln!("Error: could not pack the sprites within an area fifty times their size.");
-        std::process::exit(exitcode::DATAERR);
+    match spritesheet_builder.generate() {
+        Ok(spritesheet) => spritesheet,
+        Err(e) => {
+            eprintln!("Error: could not generate the spritesheet ({e})");
+            std::process::exit(exitcode::DATAERR);
+        }
+    }
+}
+
+/// A spritesheet image saved by [`save_spritesheet`].
+struct SavedSpritesheet {
+    /// Path of the image file.
+    path: String,
+    /// Size of the image file, in bytes.
+    size: usize,
+    /// How the image was encoded, if it's a PNG.
+    png_report: Option<PngReport>,
+}
+
+/// Encode `spritesheet` and save it, and its index file, with the file name `prefix` and the
+/// extensions for the chosen formats. Prints how the image was quantised, if `--quantize` is
+/// given. The time spent encoding and saving is added to `timings`.
+fn save_spritesheet(
+    args: &cli::BuildArgs,
+    spritesheet: &Spritesheet,
+    prefix: &str,
+    timer: &mut report::Timer,
+    timings: &mut report::Timings,
+) -> SavedSpritesheet {
+    let format = spritesheet.format();
+    let path = format!("{prefix}.{}", format.extension());
+    let encoded = if format == ImageFormat::Png {
+        spritesheet
+            .encode_png_with_report()
+            .map(|(image, report)| (image, Some(report)))
+    } else {
+        spritesheet.encode().map(|image| (image, None))
+    };
+    let (image, png_report) = match encoded {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error: could not encode spritesheet ({e})");
+            std::process::exit(exitcode::SOFTWARE);
+        }
     };
+    let size = image.len();
+    timings.encode += timer.lap();
+    if let Err(e) = std::fs::write(&path, image) {
+        eprintln!("Error: could not save spritesheet to {path} ({e})");
+        std::process::exit(exitcode::IOERR);
+    };
+    if let Some(report) = png_report
+        .as_ref()
+        .and_then(|report| report.quantization.as_ref())
+    {
+        match report.indexed_size {
+            _ if report.applied() => eprintln!(
+                "Quantised spritesheet {path} to {} colours (error {:.2}), saving {} bytes \
+                 ({:.1}%)",
+                report.colors,
+                report.error,
+                report.saving(),
+                100.0 * report.saving() as f32 / report.rgba_size as f32,
+            ),
+            Some(indexed_size) => eprintln!(
+                "Spritesheet {path} not quantised: quantised PNG ({indexed_size} bytes) is no \
+                 smaller than the original ({} bytes)",
+                report.rgba_size,
+            ),
+            None => eprintln!(
+                "Spritesheet {path} not quantised: error {:.2} is above the maximum of {}",
+                report.error,
+                args.quantize.unwrap_or_default(),
+            ),
+        }
+    }
 
-    // Save the bitmapped spritesheet to a local PNG.
-    let file_prefix = args.output;
-    let spritesheet_path = format!("{file_prefix}.png");
-    if let Err(e) = spritesheet.save_spritesheet(&spritesheet_path) {
-        eprintln!("Error: could not save spritesheet to {spritesheet_path} ({e})");
+    // Save the index file to a local file with the same name as the spritesheet.
+    let index_format = args.index_format.index_format();
+    if let Err(e) = spritesheet.save_index_with_format(prefix, index_format, args.minify_index_file)
+    {
+        eprintln!("Error: could not save sprite index to {prefix} ({e})");
         std::process::exit(exitcode::IOERR);
     };
+    timings.save += timer.lap();
+    SavedSpritesheet {
+        path,
+        size,
+        png_report,
+    }
+}
+
+/// Returns the file name, without an extension, for the spritesheet at `pixel_ratio` that goes
+/// with the spritesheet named `file_prefix`.
+///
+/// Any `@2x`-style suffix is replaced, so the spritesheet at a pixel ratio of 3 that goes with
+/// `sprite@2x` is `sprite@3x`. The spritesheet at a pixel ratio of 1 has no suffix (`sprite`),
+/// unless `file_prefix` has none either.
+fn ratio_file_prefix(file_prefix: &str, pixel_ratio: f32) -> String {
+    let base = file_prefix
+        .rsplit_once('@')
+        .filter(|(_, suffix)| {
+            suffix
+                .strip_suffix('x')
+                .is_some_and(|ratio| ratio.parse::<f32>().is_ok())
+        })
+        .map_or(file_prefix, |(base, _)| base);
+    if pixel_ratio == 1.0 && base != file_prefix {
+        base.to_string()
+    } else {
+        format!("{base}@{pixel_ratio}x")
+    }
+}
+
+/// Returns the paths of the SVGs listed in the manifest at `path` (or on stdin if `path` is
+/// `-`), found in the directory `input`.
+fn manifest_paths(path: &Path, input: &Path) -> Vec<PathBuf> {
+    let text = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    };
+    let icons = match text
+        .map_err(SpreetError::from)
+        .and_then(|text| parse_manifest(&text))
+    {
+        Ok(icons) => icons,
+        Err(e) => {
+            eprintln!("Error: could not read manifest from {path:?} ({e})");
+            std::process::exit(exitcode::NOINPUT);
+        }
+    };
+    match resolve_manifest(&icons, input) {
+        Ok(paths) => paths,
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("Error: {} in {input:?}", error.error);
+            }
+            eprintln!(
+                "Error: {} of {} icons in the manifest could not be found",
+                errors.len(),
+                icons.len(),
+            );
+            std::process::exit(exitcode::DATAERR);
+        }
+    }
+}
 
-    // Save the index file to a local JSON file with the same name as the spritesheet.
-    if let Err(e) = spritesheet.save_index(&file_prefix, args.minify_index_file) {
-        eprintln!("Error: could not save sprite index to {file_prefix} ({e})");
+/// Returns the icons used by the style at `path`, and any icons whose names match the `allow`
+/// patterns.
+fn style_icons(path: &Path, allow: &[String]) -> StyleIcons {
+    let icons = match StyleIcons::load(path) {
+        Ok(icons) => icons,
+        Err(e) => {
+            eprintln!("Error: could not read style from {path:?} ({e})");
+            std::process::exit(exitcode::NOINPUT);
+        }
+    };
+    if allow.is_empty() {
+        for layer in icons.dynamic_layers() {
+            eprintln!(
+                "Warning: icon names in layer {layer:?} depend on feature data, so some of its \
+                 icons may be left out (use --allow-icon to include them)"
+            );
+        }
+    }
+    match icons.allow(allow) {
+        Ok(icons) => icons,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(exitcode::USAGE);
+        }
+    }
+}
+
+/// Open the archive of SVGs at `path`.
+#[cfg(any(feature = "zip", feature = "tar"))]
+fn open_archive(path: &Path) -> Box<dyn SpriteSource> {
+    match ArchiveSource::open(path) {
+        Ok(archive) => Box::new(archive),
+        Err(e) => {
+            eprintln!("Error: could not read archive {path:?} ({e})");
+            std::process::exit(exitcode::NOINPUT);
+        }
+    }
+}
+
+/// Open the archive of SVGs at `path`, which isn't possible without archive support.
+#[cfg(not(any(feature = "zip", feature = "tar")))]
+fn open_archive(path: &Path) -> Box<dyn SpriteSource> {
+    eprintln!("Error: {path:?} is not a directory (this build can't read archives)");
+    std::process::exit(exitcode::NOINPUT);
+}
+
+/// Returns a description of an SVG file that couldn't be loaded.
+fn describe_load_error(error: &LoadError) -> String {
+    let path = &error.path;
+    match &error.error {
+        SpreetError::PathError(_) => format!("cannot make a valid sprite name from {path:?}"),
+        SpreetError::SpriteError { source, .. } => {
+            format!("{path:?}: could not render a sprite ({source})")
+        }
+        SpreetError::IoError(e) => format!("{path:?}: could not read file ({e})"),
+        e => format!("{path:?}: not a valid SVG image ({e})"),
+    }
+}
+
+/// Print information about a sprite cache, or prune it.
+fn cache(command: cli::CacheCommand) {
+    let dir = match &command {
+        cli::CacheCommand::Info { dir } | cli::CacheCommand::Prune { dir, .. } => dir,
+    };
+    if !dir.is_dir() {
+        eprintln!("Error: cache directory {dir:?} does not exist");
+        std::process::exit(exitcode::NOINPUT);
+    }
+    let result = SpriteCache::new(dir).and_then(|cache| match command {
+        cli::CacheCommand::Info { .. } => cache.stats().map(|stats| {
+            println!("{} cached sprites, {} bytes", stats.entries, stats.size);
+        }),
+        cli::CacheCommand::Prune { older_than, .. } => {
+            let older_than = older_than.map(|days| Duration::from_secs(days * 24 * 60 * 60));
+            cache.prune(older_than).map(|removed| {
+                println!(
+                    "Removed {} cached sprites, {} bytes",
+                    removed.entries, removed.size
+                );
+            })
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("Error: could not read cache directory {dir:?} ({e})");
         std::process::exit(exitcode::IOERR);
+    }
+}
+
+/// Check SVGs for problems, printing each one found.
+fn lint(args: cli::LintArgs) {
+    let mut paths = if args.input.is_dir() {
+        match get_svg_input_paths(&args.input, args.recursive) {
+            Ok(paths) => paths,
+            Err(e) => {
+                eprintln!("Error: could not read {:?} ({e})", args.input);
+                std::process::exit(exitcode::NOINPUT);
+            }
+        }
+    } else {
+        vec![args.input.clone()]
+    };
+    paths.sort();
+    let options = LintOptions {
+        load_options: args.load_options(),
+        max_size: args.max_size,
+        grid: args.grid,
     };
+    let mut failed = 0;
+    for path in &paths {
+        let issues = match lint_svg(path, &options) {
+            Ok(issues) => issues.iter().map(ToString::to_string).collect(),
+            Err(SpreetError::IoError(e)) => vec![format!("could not read file ({e})")],
+            Err(e) => vec![format!("not a valid SVG image ({e})")],
+        };
+        if !issues.is_empty() {
+            failed += 1;
+        }
+        for issue in issues {
+            println!("{}: {issue}", path.display());
+        }
+    }
+    if failed > 0 {
+        eprintln!("{failed} of {} SVGs have problems", paths.len());
+        std::process::exit(exitcode::DATAERR);
+    }
+    eprintln!("No problems found in {} SVGs", paths.len());
 }
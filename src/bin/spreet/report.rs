@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use spreet::{PngReport, Spritesheet};
+
+/// Measures how long each phase of a build takes.
+pub struct Timer(Instant);
+
+/// Time taken by each phase of a build, in milliseconds.
+#[derive(Default, Serialize)]
+pub struct Timings {
+    /// Finding the input SVG files.
+    pub discover: f64,
+    /// Loading and rendering the SVGs.
+    pub render: f64,
+    /// Removing duplicates and packing the sprites into a spritesheet.
+    pub pack: f64,
+    /// Encoding (and optimising) the spritesheet image.
+    pub encode: f64,
+    /// Saving the spritesheet and index files.
+    pub save: f64,
+}
+
+impl Timer {
+    pub fn start() -> Self {
+        Self(Instant::now())
+    }
+
+    /// Returns the time since the timer started or this method was last called, in milliseconds.
+    pub fn lap(&mut self) -> f64 {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.0);
+        self.0 = now;
+        elapsed.as_micros() as f64 / 1000.0
+    }
+}
+
+/// A summary of a build, saved as JSON by the `--report` argument.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    /// Number of sprites in the index file.
+    pub sprites: usize,
+    /// Number of sprites removed because they duplicated another sprite.
+    pub duplicates_removed: usize,
+    /// Width of the spritesheet, in pixels.
+    pub width: u32,
+    /// Height of the spritesheet, in pixels.
+    pub height: u32,
+    /// Total area of the sprites in the spritesheet, in pixels.
+    pub sprite_area: usize,
+    /// Area of the spritesheet, in pixels.
+    pub sheet_area: usize,
+    /// Proportion of the spritesheet covered by sprites.
+    pub packing_efficiency: f32,
+    /// Size of the PNG before it was optimised, in bytes (PNG spritesheets only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unoptimized_size: Option<usize>,
+    /// Size of the spritesheet image, in bytes.
+    pub size: usize,
+    /// Time taken by each phase of the build, in milliseconds.
+    pub timings: Timings,
+}
+
+impl Report {
+    pub fn new(
+        spritesheet: &Spritesheet,
+        size: usize,
+        png_report: Option<&PngReport>,
+        timings: Timings,
+    ) -> Self {
+        Self {
+            sprites: spritesheet.get_index().len(),
+            duplicates_removed: spritesheet
+                .duplicate_groups()
+                .iter()
+                .map(|group| group.len() - 1)
+                .sum(),
+            width: spritesheet.width(),
+            height: spritesheet.height(),
+            sprite_area: spritesheet.sprite_area(),
+            sheet_area: spritesheet.width() as usize * spritesheet.height() as usize,
+            packing_efficiency: spritesheet.packing_efficiency(),
+            unoptimized_size: png_report.map(|report| report.unoptimized_size),
+            size,
+            timings,
+        }
+    }
+
+    /// Returns a human-readable summary of the report.
+    pub fn summary(&self) -> String {
+        let t = &self.timings;
+        let total = t.discover + t.render + t.pack + t.encode + t.save;
+        let mut summary = format!(
+            "Packed {} sprites ({} duplicates removed) into a {}x{} spritesheet ({:.1}% of its area \
+             used)\n",
+            self.sprites,
+            self.duplicates_removed,
+            self.width,
+            self.height,
+            100.0 * self.packing_efficiency,
+        );
+        summary += &match self.unoptimized_size {
+            Some(unoptimized_size) => format!(
+                "Spritesheet is {} bytes ({unoptimized_size} bytes before optimisation)\n",
+                self.size
+            ),
+            None => format!("Spritesheet is {} bytes\n", self.size),
+        };
+        summary += &format!(
+            "Finished in {}: discover {}, render {}, pack {}, encode {}, save {}",
+            duration(total),
+            duration(t.discover),
+            duration(t.render),
+            duration(t.pack),
+            duration(t.encode),
+            duration(t.save),
+        );
+        summary
+    }
+
+    /// Save the report as JSON to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("report is valid JSON");
+        std::fs::write(path, json)
+    }
+}
+
+/// Format a duration given in milliseconds.
+fn duration(ms: f64) -> String {
+    format!("{:.2?}", Duration::from_secs_f64(ms / 1000.0))
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::fs::{create_dir_all, read, read_dir, remove_file, rename, write, File};
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use resvg::tiny_skia::Pixmap;
+use resvg::usvg::ShapeRendering;
+use siphasher::sip128::{Hasher128, SipHasher13};
+
+use crate::error::SpreetResult;
+use crate::fs::LoadOptions;
+use crate::sprite::RenderOptions;
+
+/// An on-disk cache of rendered sprite bitmaps.
+///
+/// Each bitmap is stored as a file of raw pixels, named after a hash of the SVG file's contents,
+/// the pixel ratio, and the options used to load and render it. When a set of icons is rebuilt
+/// only the icons that changed need to be rendered again. Pass a cache to
+/// [`SpriteLoader::cache`] to use it.
+///
+/// Only the SVG file's bytes, the pixel ratio and the options are hashed, along with the size and
+/// modification time of each font file in [`LoadOptions::fonts`]. Files referenced by an SVG
+/// image (e.g. PNGs in `<image>` elements) and system fonts aren't part of the hash, so clear the
+/// cache if those change, or sprites will be rendered from the old files.
+///
+/// [`SpriteLoader::cache`]: crate::SpriteLoader::cache
+#[derive(Clone, Debug)]
+pub struct SpriteCache {
+    dir: PathBuf,
+}
+
+/// The number and total size of entries in a [`SpriteCache`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of cached bitmaps.
+    pub entries: usize,
+    /// Total size of the cached bitmaps, in bytes.
+    pub size: u64,
+}
+
+/// File extension of cache entries.
+const EXTENSION: &str = "pixmap";
+
+/// A hash identifying a rendered sprite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct CacheKey(u128);
+
+impl CacheKey {
+    /// Hash the SVG `data` and everything else that affects how it's rendered.
+    pub(crate) fn new(
+        data: &[u8],
+        pixel_ratio: f32,
+        load_options: &LoadOptions,
+        render_options: &RenderOptions,
+        sdf: bool,
+    ) -> Self {
+        let mut hasher = SipHasher13::new();
+        // Rendering can change between versions of spreet (and its dependencies), so a new version
+        // starts with an empty cache.
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        data.hash(&mut hasher);
+        pixel_ratio.to_bits().hash(&mut hasher);
+        shape_rendering_id(load_options.shape_rendering).hash(&mut hasher);
+        load_options.fonts.hash(&mut hasher);
+        font_versions(&load_options.fonts).hash(&mut hasher);
+        load_options.system_fonts.hash(&mut hasher);
+        load_options.font_family.hash(&mut hasher);
+        render_options.hash(&mut hasher);
+        sdf.hash(&mut hasher);
+        Self(hasher.finish128().as_u128())
+    }
+
+    fn file_name(&self) -> String {
+        format!("{:032x}.{EXTENSION}", self.0)
+    }
+}
+
+/// Returns a hash of the path, size and modification time of every font file in `fonts` (font
+/// files, and directories of font files), so that sprites are rendered again when a font changes.
+///
+/// Like the font database they're loaded into, each set of fonts is only looked at once.
+fn font_versions(fonts: &[PathBuf]) -> u128 {
+    static VERSIONS: OnceLock<Mutex<HashMap<Vec<PathBuf>, u128>>> = OnceLock::new();
+
+    if fonts.is_empty() {
+        return 0;
+    }
+    let mut versions = VERSIONS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    *versions.entry(fonts.to_vec()).or_insert_with(|| {
+        let mut files = Vec::new();
+        for path in fonts {
+            collect_font_files(path, &mut files);
+        }
+        files.sort();
+        let mut hasher = SipHasher13::new();
+        files.hash(&mut hasher);
+        hasher.finish128().as_u128()
+    })
+}
+
+/// Add the path, size and modification time of the font file at `path`, or of every file in the
+/// directory at `path` and its sub-directories, to `files`. Files that can't be read are left
+/// out, as they are when fonts are loaded.
+fn collect_font_files(path: &Path, files: &mut Vec<(PathBuf, u64, Option<SystemTime>)>) {
+    if path.is_dir() {
+        for entry in read_dir(path).into_iter().flatten().flatten() {
+            collect_font_files(&entry.path(), files);
+        }
+    } else if let Ok(metadata) = path.metadata() {
+        files.push((path.to_path_buf(), metadata.len(), metadata.modified().ok()));
+    }
+}
+
+/// A stable identifier for usvg's `ShapeRendering`, which doesn't implement `Hash`.
+fn shape_rendering_id(mode: ShapeRendering) -> u8 {
+    match mode {
+        ShapeRendering::OptimizeSpeed => 0,
+        ShapeRendering::CrispEdges => 1,
+        ShapeRendering::GeometricPrecision => 2,
+    }
+}
+
+/// Returns `true` if `path` looks like a file written by a [`SpriteCache`].
+fn is_cache_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == EXTENSION)
+        && path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| stem.len() == 32 && stem.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+impl SpriteCache {
+    /// Open the cache in directory `dir`, creating the directory if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory can't be created.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> SpreetResult<Self> {
+        let dir = dir.into();
+        create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The directory the cache is stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the cached bitmap for `key`, or `None` if there isn't one (or it can't be read).
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<Pixmap> {
+        let path = self.dir.join(key.file_name());
+        let data = read(&path).ok()?;
+        // Entries are the bitmap's width and height as little-endian `u32`s, followed by its
+        // premultiplied RGBA pixels. Storing the pixels as they are keeps them identical to a
+        // freshly-rendered bitmap.
+        let (size, pixels) = data.split_first_chunk::<8>()?;
+        let width = u32::from_le_bytes(size[..4].try_into().ok()?);
+        let height = u32::from_le_bytes(size[4..].try_into().ok()?);
+        let mut pixmap = Pixmap::new(width, height)?;
+        if pixmap.data().len() != pixels.len() {
+            return None;
+        }
+        pixmap.data_mut().copy_from_slice(pixels);
+        // Mark the entry as recently used, so it isn't pruned.
+        if let Ok(file) = File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(pixmap)
+    }
+
+    /// Store the bitmap for `key`.
+    pub(crate) fn put(&self, key: &CacheKey, pixmap: &Pixmap) -> SpreetResult<()> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let mut data = Vec::with_capacity(8 + pixmap.data().len());
+        data.extend(pixmap.width().to_le_bytes());
+        data.extend(pixmap.height().to_le_bytes());
+        data.extend(pixmap.data());
+        // Write to a temporary file and rename it, so other threads and processes never read a
+        // partly-written entry.
+        let tmp_path = self.dir.join(format!(
+            ".{}.{}.{}",
+            key.file_name(),
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        write(&tmp_path, data)?;
+        rename(&tmp_path, self.dir.join(key.file_name()))?;
+        Ok(())
+    }
+
+    /// Returns the number and total size of entries in the cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory can't be read.
+    pub fn stats(&self) -> SpreetResult<CacheStats> {
+        let mut stats = CacheStats::default();
+        for (_, metadata) in self.entries()? {
+            stats.entries += 1;
+            stats.size += metadata.len();
+        }
+        Ok(stats)
+    }
+
+    /// Remove entries that haven't been used for at least `older_than`, or every entry if
+    /// `older_than` is `None`. Returns the number and total size of the removed entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory can't be read or an entry can't be removed.
+    pub fn prune(&self, older_than: Option<Duration>) -> SpreetResult<CacheStats> {
+        let now = SystemTime::now();
+        let mut removed = CacheStats::default();
+        for (path, metadata) in self.entries()? {
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if older_than.is_none_or(|older_than| age >= older_than) {
+                remove_file(path)?;
+                removed.entries += 1;
+                removed.size += metadata.len();
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Returns the path and metadata of every entry in the cache.
+    fn entries(&self) -> SpreetResult<Vec<(PathBuf, std::fs::Metadata)>> {
+        let mut entries = Vec::new();
+        for entry in read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if is_cache_file(&path) {
+                entries.push((path, entry.metadata()?));
+            }
+        }
+        Ok(entries)
+    }
+}
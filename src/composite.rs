@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+use std::fs::read;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::SpreetResult;
+
+/// A set of composite sprites, each made by drawing layers of SVG images on top of each other.
+///
+/// Every combination of [`values`](Composition::values) makes one sprite. For example, values
+/// for `pin`, `glyph` and `color` make one sprite for each pin, glyph and colour. The layers'
+/// paths and the name can contain placeholders for the values (e.g. `pins/{pin}.svg`), and so can
+/// the layers' SVG images themselves (e.g. `fill="{color}"`), as in a
+/// [`SpriteTemplate`](crate::SpriteTemplate).
+#[derive(Clone, Debug, Deserialize)]
+pub struct Composition {
+    /// Pattern used to name each sprite, containing placeholders (e.g. `poi-{glyph}-{color}`).
+    pub name: String,
+    /// The layers, from bottom to top. The first layer is the base image, which sets the sprite's
+    /// size and metadata.
+    pub layers: Vec<Layer>,
+    /// The values for each placeholder.
+    #[serde(default)]
+    pub values: BTreeMap<String, Vec<String>>,
+}
+
+/// One layer of a [`Composition`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct Layer {
+    /// Path to the layer's SVG image, which can contain placeholders.
+    pub source: String,
+    /// Position of the layer's top-left corner, in the base image's pixels. Ignored for the base
+    /// image.
+    #[serde(default)]
+    pub offset: [f32; 2],
+    /// Factor by which the layer is scaled. Ignored for the base image.
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+impl Composition {
+    /// Returns every combination of values, keyed by placeholder name.
+    ///
+    /// Combinations are ordered by the values of each placeholder in turn, with placeholders
+    /// sorted by name.
+    pub fn combinations(&self) -> Vec<BTreeMap<String, String>> {
+        let mut combinations = vec![BTreeMap::new()];
+        for (name, values) in &self.values {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.insert(name.clone(), value.clone());
+                        combination
+                    })
+                })
+                .collect();
+        }
+        combinations
+    }
+}
+
+/// Read a list of [`Composition`]s from a JSON file. Layer paths are relative to the file.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or isn't a valid list of compositions.
+pub fn load_compositions<P: AsRef<Path>>(path: P) -> SpreetResult<Vec<Composition>> {
+    let path = path.as_ref();
+    let mut compositions: Vec<Composition> = serde_json::from_slice(&read(path)?)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for layer in compositions.iter_mut().flat_map(|c| &mut c.layers) {
+        layer.source = dir.join(&layer.source).to_string_lossy().into_owned();
+    }
+    Ok(compositions)
+}
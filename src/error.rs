@@ -2,12 +2,16 @@ use std::io;
 use std::path::PathBuf;
 
 use oxipng::PngError;
+use sdf_glyph_renderer::SdfGlyphError;
 use thiserror::Error;
 
 pub type SpreetResult<T> = Result<T, SpreetError>;
 
 /// Errors encountered during execution.
+///
+/// Some variants only exist when a Cargo feature is enabled, so the enum is non-exhaustive.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum SpreetError {
     #[error("i/o error: {0}")]
     IoError(#[from] io::Error),
@@ -19,4 +23,76 @@ pub enum SpreetError {
     OxiPngError(#[from] PngError),
     #[error("SVG error: {0}")]
     SvgError(#[from] resvg::usvg::Error),
+    #[error("SDF error: {0}")]
+    SdfError(#[from] SdfGlyphError),
+    #[error("Image would be empty ({width}x{height} pixels)")]
+    EmptyImage { width: u32, height: u32 },
+    #[error("Image is too large ({width}x{height} pixels)")]
+    TooLarge { width: u32, height: u32 },
+    #[error(
+        "Could not pack the sprites into a spritesheet (they cover {required_area} pixels, and \
+         the largest is {largest:?})"
+    )]
+    PackingFailed {
+        required_area: usize,
+        /// Name of the sprite with the longest side, which is the most likely to not fit.
+        largest: String,
+    },
+    #[error("Could not render sprite {name:?}: {source}")]
+    SpriteError {
+        /// Name of the sprite that couldn't be rendered.
+        name: String,
+        /// The reason it couldn't be rendered, such as [`SpreetError::EmptyImage`],
+        /// [`SpreetError::TooLarge`] or [`SpreetError::SdfError`].
+        source: Box<SpreetError>,
+    },
+    #[error("Only PNG spritesheets can be quantised")]
+    QuantizeUnsupported,
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("No value for template placeholder {{{0}}}")]
+    MissingTemplateValue(String),
+    #[error("Invalid pattern: {0}")]
+    PatternError(#[from] ignore::Error),
+    #[error("No SVG found for icon {0:?}")]
+    MissingIcon(String),
+    #[cfg(feature = "webp")]
+    #[error("WebP encoding error: {0}")]
+    WebpError(String),
+    #[cfg(feature = "avif")]
+    #[error("AVIF encoding error: {0}")]
+    AvifError(#[from] ravif::Error),
+    #[cfg(any(feature = "zip", feature = "tar"))]
+    #[error("{} in the archive is larger than {max_size} bytes", .path.display())]
+    ArchiveFileTooLarge {
+        /// Path of the file in the archive.
+        path: PathBuf,
+        /// The [`max_file_size`](crate::ArchiveOptions::max_file_size) it exceeds.
+        max_size: u64,
+    },
+    #[cfg(feature = "zip")]
+    #[error("Zip archive error: {0}")]
+    ZipError(#[from] zip::result::ZipError),
+}
+
+impl SpreetError {
+    /// Add the `name` of the sprite that caused the error.
+    pub(crate) fn for_sprite(self, name: &str) -> Self {
+        SpreetError::SpriteError {
+            name: name.to_string(),
+            source: Box::new(self),
+        }
+    }
+}
+
+/// An error encountered while loading one file in a batch of SVG files, such as by
+/// [`SpriteLoader`](crate::SpriteLoader).
+#[derive(Debug, Error)]
+#[error("{}: {error}", .path.display())]
+pub struct LoadError {
+    /// Path to the file that couldn't be loaded.
+    pub path: PathBuf,
+    /// The reason it couldn't be loaded.
+    #[source]
+    pub error: SpreetError,
 }
@@ -1,10 +1,17 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs::{read, read_dir, DirEntry};
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock};
 
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
 use resvg::usvg::fontdb::Database;
-use resvg::usvg::{decompress_svgz, roxmltree, Error as UsvgError, Options, Tree};
+use resvg::usvg::{
+    decompress_svgz, roxmltree, Error as UsvgError, ImageHrefResolver, Options, ShapeRendering,
+    Tree,
+};
 
 use crate::error::SpreetResult;
 
@@ -30,80 +37,426 @@ fn is_useful_input(entry: &DirEntry) -> bool {
     !is_hidden(entry) && is_svg_file(entry)
 }
 
+/// The name of the files that list SVGs to leave out of a spritesheet.
+pub const IGNORE_FILE_NAME: &str = ".spreetignore";
+
+/// Options that choose which SVG files in a directory are used, by
+/// [`get_svg_input_paths_with_filter`], or which images in a
+/// [`SpriteSource`](crate::SpriteSource) are used, by
+/// [`SpriteSource::svg_paths_with_filter`](crate::SpriteSource::svg_paths_with_filter).
+///
+/// Patterns use the same syntax as a `.gitignore` file, and are matched against paths relative to
+/// the input directory (or the root of the source). A pattern without a `/` matches a file or
+/// directory name at any depth, so `drafts` excludes every directory called `drafts`.
+#[derive(Clone, Debug)]
+pub struct InputFilter {
+    /// If not empty, only SVG files that match one of these patterns are used (e.g.
+    /// `transport/**`).
+    pub include: Vec<String>,
+    /// Files and directories that match any of these patterns aren't used, even if they match an
+    /// `include` pattern.
+    pub exclude: Vec<String>,
+    /// Whether `.spreetignore` files are read. A `.spreetignore` file can be at any level of the
+    /// input directory, and lists files and directories to leave out using `.gitignore` syntax.
+    /// The default is `true`.
+    pub ignore_files: bool,
+}
+
+impl Default for InputFilter {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            ignore_files: true,
+        }
+    }
+}
+
 /// Returns a vector of file paths matching all SVG and SVGZ files within the given directory.
 ///
-/// It ignores hidden files (files whose names begin with `.`) but it does follow symlinks. If
-/// `recursive` is `true` it will also return file paths in sub-directories.
+/// It ignores hidden files (files whose names begin with `.`) and anything listed in a
+/// `.spreetignore` file, but it does follow symlinks. If `recursive` is `true` it will also
+/// return file paths in sub-directories.
 ///
 /// # Errors
 ///
-/// This function will return an error if Rust's underlying [`read_dir`] returns an error.
+/// This function will return an error if Rust's underlying [`read_dir`] returns an error, or if a
+/// `.spreetignore` file is invalid.
 pub fn get_svg_input_paths<P: AsRef<Path>>(path: P, recursive: bool) -> SpreetResult<Vec<PathBuf>> {
+    get_svg_input_paths_with_filter(path, recursive, &InputFilter::default())
+}
+
+/// Returns a vector of file paths matching the SVG and SVGZ files within the given directory that
+/// pass `filter`. See [`get_svg_input_paths`].
+///
+/// # Errors
+///
+/// This function will return an error if Rust's underlying [`read_dir`] returns an error, or if a
+/// pattern or `.spreetignore` file is invalid.
+pub fn get_svg_input_paths_with_filter<P: AsRef<Path>>(
+    path: P,
+    recursive: bool,
+    filter: &InputFilter,
+) -> SpreetResult<Vec<PathBuf>> {
+    let path = path.as_ref();
+    let overrides = build_overrides(path, filter)?;
+    let mut results = Vec::new();
+    collect_svg_paths(
+        path,
+        recursive,
+        filter,
+        &overrides,
+        &mut Vec::new(),
+        &mut results,
+    )?;
+    Ok(results)
+}
+
+/// Returns the paths in `paths` that pass `filter`. The paths are relative to the root of a
+/// collection of files, such as an archive, and `read_file` returns the contents of a file in the
+/// collection (or `None` if there isn't one), which is used to read `.spreetignore` files.
+///
+/// A path is left out if it, or any of its parent directories, is filtered out, just as
+/// [`get_svg_input_paths_with_filter`] doesn't look inside directories that are filtered out.
+pub(crate) fn filter_relative_paths<F>(
+    paths: Vec<PathBuf>,
+    filter: &InputFilter,
+    read_file: F,
+) -> SpreetResult<Vec<PathBuf>>
+where
+    F: Fn(&Path) -> Option<Vec<u8>>,
+{
+    let overrides = build_overrides(Path::new(""), filter)?;
+    // The rules from the `.spreetignore` file in each directory, if it has one.
+    let mut ignore_files: HashMap<PathBuf, Option<Gitignore>> = HashMap::new();
     let mut results = Vec::new();
-    for entry in read_dir(path)? {
+    'paths: for path in paths {
+        // The root and each directory above the file, outermost first, then the file itself.
+        let mut ancestors = path.ancestors().collect::<Vec<_>>();
+        ancestors.reverse();
+        let mut ignores = Vec::new();
+        for (i, ancestor) in ancestors.iter().enumerate() {
+            let is_dir = i + 1 < ancestors.len();
+            if i > 0 && is_filtered_out(ancestor, is_dir, &overrides, &ignores) {
+                continue 'paths;
+            }
+            if is_dir && filter.ignore_files {
+                if !ignore_files.contains_key(*ancestor) {
+                    let gitignore = read_file(&ancestor.join(IGNORE_FILE_NAME))
+                        .map(|data| parse_ignore_file(ancestor, &data))
+                        .transpose()?;
+                    ignore_files.insert(ancestor.to_path_buf(), gitignore);
+                }
+                if let Some(Some(gitignore)) = ignore_files.get(*ancestor) {
+                    ignores.push(gitignore.clone());
+                }
+            }
+        }
+        results.push(path);
+    }
+    Ok(results)
+}
+
+/// Returns the include and exclude patterns in `filter`, matched against paths in `root`.
+fn build_overrides(root: &Path, filter: &InputFilter) -> SpreetResult<Override> {
+    let mut builder = OverrideBuilder::new(root);
+    for pattern in &filter.include {
+        builder.add(pattern)?;
+    }
+    // Later patterns take precedence, so exclusions are added last.
+    for pattern in &filter.exclude {
+        builder.add(&format!("!{pattern}"))?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Returns the rules in `data`, the contents of the `.spreetignore` file in the directory `dir`.
+fn parse_ignore_file(dir: &Path, data: &[u8]) -> SpreetResult<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    for line in String::from_utf8_lossy(data).lines() {
+        builder.add_line(None, line)?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Add the paths of the SVG files in `dir` that aren't filtered out to `results`. `ignores` holds
+/// the rules from the `.spreetignore` files in `dir` and its parents, outermost first.
+fn collect_svg_paths(
+    dir: &Path,
+    recursive: bool,
+    filter: &InputFilter,
+    overrides: &Override,
+    ignores: &mut Vec<Gitignore>,
+    results: &mut Vec<PathBuf>,
+) -> SpreetResult<()> {
+    let ignore_file = dir.join(IGNORE_FILE_NAME);
+    let has_ignore_file = filter.ignore_files && ignore_file.is_file();
+    if has_ignore_file {
+        let (gitignore, error) = Gitignore::new(ignore_file);
+        if let Some(error) = error {
+            return Err(error.into());
+        }
+        ignores.push(gitignore);
+    }
+    for entry in read_dir(dir)? {
         let entry = entry?;
         let path_buf = entry.path();
-        if recursive && path_buf.is_dir() {
-            let nested = get_svg_input_paths(path_buf, true)?;
-            results.extend(nested);
+        let is_dir = path_buf.is_dir();
+        if is_filtered_out(&path_buf, is_dir, overrides, ignores) {
+            continue;
+        }
+        if recursive && is_dir {
+            collect_svg_paths(&path_buf, true, filter, overrides, ignores, results)?;
         } else if is_useful_input(&entry) {
             results.push(path_buf);
         }
     }
-    Ok(results)
+    if has_ignore_file {
+        ignores.pop();
+    }
+    Ok(())
+}
+
+/// Returns `true` if `path` is excluded by the include and exclude patterns in `overrides`, or by
+/// the rules in `ignores`.
+fn is_filtered_out(path: &Path, is_dir: bool, overrides: &Override, ignores: &[Gitignore]) -> bool {
+    if overrides.matched(path, is_dir).is_ignore() {
+        return true;
+    }
+    // Rules in deeper `.spreetignore` files take precedence, as in `.gitignore` files.
+    ignores
+        .iter()
+        .rev()
+        .map(|gitignore| gitignore.matched(path, is_dir))
+        .find(|m| !m.is_none())
+        .is_some_and(|m| m.is_ignore())
+}
+
+/// Options that control how an SVG image is parsed.
+#[derive(Clone, Debug)]
+pub struct LoadOptions {
+    /// The antialiasing mode for shapes that don't set their own [`shape-rendering`] property.
+    /// Shapes that do set the property keep their own mode.
+    ///
+    /// [`shape-rendering`]: https://developer.mozilla.org/en-US/docs/Web/SVG/Attribute/shape-rendering
+    pub shape_rendering: ShapeRendering,
+    /// Font files, and directories of font files, used to render text. Directories are searched
+    /// recursively.
+    pub fonts: Vec<PathBuf>,
+    /// Whether fonts installed on the system are also used to render text. Text rendered with
+    /// system fonts can look different on each computer, so turn this off and use [`fonts`] to
+    /// render text the same way everywhere. The default is `true`.
+    ///
+    /// [`fonts`]: LoadOptions::fonts
+    pub system_fonts: bool,
+    /// The font family used for text that doesn't set a `font-family`, for the generic families
+    /// (`serif`, `sans-serif`, etc.), and as a fallback when none of the text's font families are
+    /// available. If `None`, usvg's defaults are used.
+    pub font_family: Option<String>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            shape_rendering: ShapeRendering::default(),
+            fonts: Vec::new(),
+            system_fonts: true,
+            font_family: None,
+        }
+    }
+}
+
+/// Resolves the URL of a resource referred to by an SVG image, such as the `href` of an `<image>`
+/// element, and returns its data.
+///
+/// Use a resolver with [`load_svg_from_bytes`] to load resources from somewhere other than the
+/// filesystem, e.g. a database. Any function or closure that takes a URL and returns the data it
+/// refers to (or `None` if there's no such resource) is a resolver. The data can be a PNG, JPEG,
+/// GIF, WebP or SVG image.
+pub trait ResourceResolver: Send + Sync {
+    /// Returns the data for the resource at `href`, or `None` if it can't be found.
+    fn resolve(&self, href: &str) -> Option<Vec<u8>>;
+}
+
+impl<F> ResourceResolver for F
+where
+    F: Fn(&str) -> Option<Vec<u8>> + Send + Sync,
+{
+    fn resolve(&self, href: &str) -> Option<Vec<u8>> {
+        self(href)
+    }
+}
+
+/// Where the resources referred to by an SVG image are loaded from.
+#[derive(Clone, Copy)]
+enum Resources<'a> {
+    /// Files relative to the SVG file at this path.
+    File(&'a Path),
+    /// A resolver, or nowhere if `None`.
+    Resolver(Option<&'a dyn ResourceResolver>),
 }
 
 /// Load an SVG image from a file path.
 pub fn load_svg<P: AsRef<Path>>(path: P) -> SpreetResult<Tree> {
-    static SYSTEM_FONTDB: OnceLock<Arc<Database>> = OnceLock::new();
-    static EMPTY_FONTDB: OnceLock<Arc<Database>> = OnceLock::new();
+    load_svg_with_options(path, &LoadOptions::default())
+}
 
+/// Load an SVG image from a file path, parsing it using the given `options`.
+pub fn load_svg_with_options<P: AsRef<Path>>(path: P, options: &LoadOptions) -> SpreetResult<Tree> {
     let path = path.as_ref();
-    let data = read(path)?;
-    let text = svg_data_to_text(&data)?;
+    parse_svg(&read(path)?, path, options)
+}
+
+/// Load an SVG image from SVG (or SVGZ) data in memory, parsing it using the given `options`.
+///
+/// Resources the image refers to, such as PNGs in `<image>` elements, are loaded with `resolver`.
+/// Without a resolver only resources embedded in the image (as `data:` URLs) are loaded, and the
+/// filesystem is never read.
+pub fn load_svg_from_bytes(
+    data: &[u8],
+    resolver: Option<&dyn ResourceResolver>,
+    options: &LoadOptions,
+) -> SpreetResult<Tree> {
+    let text = svg_data_to_text(data)?;
+    let doc = parse_document(&text)?;
+    tree_from_document_with_resources(&doc, Resources::Resolver(resolver), options)
+}
+
+/// Load an SVG image by reading SVG (or SVGZ) data from `reader`. See [`load_svg_from_bytes`].
+pub fn load_svg_from_reader<R: Read>(
+    mut reader: R,
+    resolver: Option<&dyn ResourceResolver>,
+    options: &LoadOptions,
+) -> SpreetResult<Tree> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    load_svg_from_bytes(&data, resolver, options)
+}
+
+/// Parse SVG data read from the file at `path`, using the given `options`.
+pub(crate) fn parse_svg(data: &[u8], path: &Path, options: &LoadOptions) -> SpreetResult<Tree> {
+    let text = svg_data_to_text(data)?;
+    let doc = parse_document(&text)?;
+    tree_from_document(&doc, path, options)
+}
+
+/// Parse SVG text into an XML document.
+pub(crate) fn parse_document(text: &str) -> Result<roxmltree::Document<'_>, UsvgError> {
     let xml_opt = roxmltree::ParsingOptions {
         allow_dtd: true,
         ..Default::default()
     };
-    let doc = roxmltree::Document::parse_with_options(&text, xml_opt).map_err(UsvgError::from)?;
-    // Font database initialisation can be expensive, so only load system fonts if an SVG includes a
-    // text element.
-    let fontdb = if svg_has_text_nodes(&doc) {
-        SYSTEM_FONTDB
-            .get_or_init(|| {
-                let mut db = Database::new();
-                db.load_system_fonts();
-                Arc::new(db)
-            })
-            .clone()
+    roxmltree::Document::parse_with_options(text, xml_opt).map_err(UsvgError::from)
+}
+
+/// Convert an SVG document read from the file at `path` into a tree, using the given `options`.
+pub(crate) fn tree_from_document(
+    doc: &roxmltree::Document,
+    path: &Path,
+    options: &LoadOptions,
+) -> SpreetResult<Tree> {
+    tree_from_document_with_resources(doc, Resources::File(path), options)
+}
+
+/// Convert an SVG document into a tree, loading the resources it refers to from `resources` and
+/// using the given `options`.
+fn tree_from_document_with_resources(
+    doc: &roxmltree::Document,
+    resources: Resources,
+    options: &LoadOptions,
+) -> SpreetResult<Tree> {
+    static EMPTY_FONTDB: OnceLock<Arc<Database>> = OnceLock::new();
+
+    // Font database initialisation can be expensive, so only load fonts if an SVG includes a text
+    // element.
+    let fontdb = if svg_has_text_nodes(doc) {
+        font_database(options)?
     } else {
         EMPTY_FONTDB
             .get_or_init(|| Arc::new(Database::new()))
             .clone()
     };
 
-    // The resources directory needs to be the same location as the SVG file itself, so that any
-    // embedded resources (like PNGs in <image> elements) that use relative URLs can be resolved
-    // correctly.
-    let resources_dir = std::fs::canonicalize(path)
-        .ok()
-        .and_then(|p| p.parent().map(Path::to_path_buf));
-    let options = Options {
-        resources_dir,
+    let mut usvg_options = Options {
         fontdb,
+        shape_rendering: options.shape_rendering,
         ..Options::default()
     };
+    match resources {
+        Resources::File(path) => {
+            // The resources directory needs to be the same location as the SVG file itself, so
+            // that any embedded resources (like PNGs in <image> elements) that use relative URLs
+            // can be resolved correctly.
+            usvg_options.resources_dir = std::fs::canonicalize(path)
+                .ok()
+                .and_then(|p| p.parent().map(Path::to_path_buf));
+        }
+        Resources::Resolver(resolver) => {
+            usvg_options.image_href_resolver.resolve_string =
+                Box::new(move |href: &str, opts: &Options| {
+                    let data = resolver?.resolve(href)?;
+                    // Let usvg work out the format from the data itself.
+                    (ImageHrefResolver::default_data_resolver())("text/plain", Arc::new(data), opts)
+                });
+        }
+    }
+    if let Some(family) = &options.font_family {
+        usvg_options.font_family.clone_from(family);
+    }
+
+    Ok(Tree::from_xmltree(doc, &usvg_options)?)
+}
+
+/// Returns a font database containing the fonts given in `options`.
+///
+/// Each distinct set of fonts is loaded once and shared by every SVG image parsed with it.
+fn font_database(options: &LoadOptions) -> SpreetResult<Arc<Database>> {
+    type FontKey = (Vec<PathBuf>, bool, Option<String>);
+    static FONTDBS: OnceLock<Mutex<HashMap<FontKey, Arc<Database>>>> = OnceLock::new();
 
-    Ok(Tree::from_xmltree(&doc, &options)?)
+    let key = (
+        options.fonts.clone(),
+        options.system_fonts,
+        options.font_family.clone(),
+    );
+    // Hold the lock while loading fonts, so that SVGs parsed in parallel don't each load them.
+    let mut fontdbs = FONTDBS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(db) = fontdbs.get(&key) {
+        return Ok(db.clone());
+    }
+    let mut db = Database::new();
+    if options.system_fonts {
+        db.load_system_fonts();
+    }
+    for path in &options.fonts {
+        if path.is_dir() {
+            db.load_fonts_dir(path);
+        } else {
+            db.load_font_file(path)?;
+        }
+    }
+    if let Some(family) = &options.font_family {
+        db.set_serif_family(family);
+        db.set_sans_serif_family(family);
+        db.set_cursive_family(family);
+        db.set_fantasy_family(family);
+        db.set_monospace_family(family);
+    }
+    let db = Arc::new(db);
+    fontdbs.insert(key, db.clone());
+    Ok(db)
 }
 
 /// Returns `true` if the SVG document contains any `<text>` nodes, `false` otherwise.
-fn svg_has_text_nodes(doc: &roxmltree::Document) -> bool {
+pub(crate) fn svg_has_text_nodes(doc: &roxmltree::Document) -> bool {
     doc.descendants().any(|n| n.has_tag_name("text"))
 }
 
 /// Convert SVG data (which may be compressed as SVGZ) into a UTF-8 string.
-fn svg_data_to_text(data: &[u8]) -> Result<Cow<'_, str>, UsvgError> {
+pub(crate) fn svg_data_to_text(data: &[u8]) -> Result<Cow<'_, str>, UsvgError> {
     if data.starts_with(&[0x1f, 0x8b]) {
         let data = decompress_svgz(data)?;
         let text = String::from_utf8(data).map_err(|_| UsvgError::NotAnUtf8Str)?;
@@ -2,11 +2,35 @@
 // spreet to import resvg separately and manage version compatibility.
 pub use resvg;
 
+mod cache;
+pub use cache::*;
+
+mod composite;
+pub use composite::*;
+
 mod error;
-pub use error::{SpreetError, SpreetResult};
+pub use error::{LoadError, SpreetError, SpreetResult};
 
 mod fs;
 pub use fs::*;
 
+mod lint;
+pub use lint::*;
+
+mod loader;
+pub use loader::*;
+
+mod manifest;
+pub use manifest::*;
+
+mod source;
+pub use source::*;
+
 mod sprite;
 pub use sprite::*;
+
+mod style;
+pub use style::*;
+
+mod template;
+pub use template::*;
@@ -0,0 +1,169 @@
+use std::fmt;
+use std::fs::read;
+use std::path::Path;
+
+use resvg::usvg::{roxmltree, Tree};
+
+use crate::error::SpreetResult;
+use crate::fs::{
+    parse_document, svg_data_to_text, svg_has_text_nodes, tree_from_document, LoadOptions,
+};
+
+/// Options that control which problems [`lint_svg`] reports.
+#[derive(Clone, Debug)]
+pub struct LintOptions {
+    /// Options used to parse the SVG image.
+    pub load_options: LoadOptions,
+    /// The largest width or height an image can have, in pixels, before it's reported as too
+    /// large. The default is 512.
+    pub max_size: f32,
+    /// If set, report images whose width or height isn't a multiple of this many pixels.
+    pub grid: Option<u32>,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            load_options: LoadOptions::default(),
+            max_size: 512.0,
+            grid: None,
+        }
+    }
+}
+
+/// A problem found in an SVG image by [`lint_svg`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum LintIssue {
+    /// The image embeds a raster image (e.g. a PNG in a `data:` URL).
+    EmbeddedRaster,
+    /// The image refers to another file or a URL.
+    ExternalReference(String),
+    /// The root `<svg>` element has no `viewBox` attribute, so the image can't be scaled
+    /// reliably.
+    MissingViewBox,
+    /// The image contains text, which is rendered with whatever system fonts are installed. Not
+    /// reported if [`LoadOptions::system_fonts`] is turned off.
+    SystemFontText,
+    /// The image is wider or taller than [`LintOptions::max_size`].
+    TooLarge { width: f32, height: f32 },
+    /// The element with this id, which marks a stretchable or content area, extends beyond the
+    /// image.
+    MarkerOutOfBounds(String),
+    /// The image's width or height isn't a multiple of [`LintOptions::grid`].
+    OffGrid { width: f32, height: f32, grid: u32 },
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmbeddedRaster => write!(f, "embeds a raster image"),
+            Self::ExternalReference(href) => write!(f, "refers to an external resource {href:?}"),
+            Self::MissingViewBox => write!(f, "has no viewBox"),
+            Self::SystemFontText => {
+                write!(
+                    f,
+                    "contains text that relies on system fonts (convert it to paths)"
+                )
+            }
+            Self::TooLarge { width, height } => write!(f, "is too large ({width}x{height})"),
+            Self::MarkerOutOfBounds(id) => write!(f, "has a {id:?} marker outside the image"),
+            Self::OffGrid {
+                width,
+                height,
+                grid,
+            } => write!(f, "is {width}x{height}, which isn't a multiple of {grid}"),
+        }
+    }
+}
+
+/// Check the SVG image at `path` for problems that make it a poor source for a sprite.
+///
+/// Returns the problems found, if any. See [`LintIssue`] for the kinds of problem reported.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or isn't a valid SVG image.
+pub fn lint_svg<P: AsRef<Path>>(path: P, options: &LintOptions) -> SpreetResult<Vec<LintIssue>> {
+    let path = path.as_ref();
+    let data = read(path)?;
+    let text = svg_data_to_text(&data)?;
+    let doc = parse_document(&text)?;
+    let tree = tree_from_document(&doc, path, &options.load_options)?;
+
+    let mut issues = Vec::new();
+    lint_references(&doc, &mut issues);
+    if !doc.root_element().has_attribute("viewBox") {
+        issues.push(LintIssue::MissingViewBox);
+    }
+    if options.load_options.system_fonts && svg_has_text_nodes(&doc) {
+        issues.push(LintIssue::SystemFontText);
+    }
+    lint_size(&tree, options, &mut issues);
+    lint_markers(&doc, &tree, &mut issues);
+    Ok(issues)
+}
+
+/// Report embedded raster images and references to other files.
+fn lint_references(doc: &roxmltree::Document, issues: &mut Vec<LintIssue>) {
+    for node in doc.descendants().filter(roxmltree::Node::is_element) {
+        // Match both `href` and `xlink:href`.
+        let Some(href) = node
+            .attributes()
+            .find(|attr| attr.name() == "href")
+            .map(|attr| attr.value().trim())
+        else {
+            continue;
+        };
+        if href.starts_with('#') {
+            continue;
+        }
+        if href.starts_with("data:") {
+            if node.has_tag_name("image") && !href.starts_with("data:image/svg") {
+                issues.push(LintIssue::EmbeddedRaster);
+            }
+        } else {
+            issues.push(LintIssue::ExternalReference(href.to_string()));
+        }
+    }
+}
+
+/// Report images that are too large or don't fit the grid.
+fn lint_size(tree: &Tree, options: &LintOptions, issues: &mut Vec<LintIssue>) {
+    let (width, height) = (tree.size().width(), tree.size().height());
+    if width > options.max_size || height > options.max_size {
+        issues.push(LintIssue::TooLarge { width, height });
+    }
+    if let Some(grid) = options.grid.filter(|grid| *grid > 0) {
+        let on_grid = |length: f32| (length / grid as f32).fract() == 0.0;
+        if !on_grid(width) || !on_grid(height) {
+            issues.push(LintIssue::OffGrid {
+                width,
+                height,
+                grid,
+            });
+        }
+    }
+}
+
+/// Report stretchable and content area markers that extend beyond the image.
+fn lint_markers(doc: &roxmltree::Document, tree: &Tree, issues: &mut Vec<LintIssue>) {
+    // Allow for rounding errors in transformed bounding boxes.
+    const TOLERANCE: f32 = 0.01;
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let ids = doc
+        .descendants()
+        .filter_map(|node| node.attribute("id"))
+        .filter(|id| id.starts_with("mapbox-stretch") || *id == "mapbox-content");
+    for id in ids {
+        let Some(bbox) = tree.node_by_id(id).map(|node| node.abs_bounding_box()) else {
+            continue;
+        };
+        if bbox.left() < -TOLERANCE
+            || bbox.top() < -TOLERANCE
+            || bbox.right() > width + TOLERANCE
+            || bbox.bottom() > height + TOLERANCE
+        {
+            issues.push(LintIssue::MarkerOutOfBounds(id.to_string()));
+        }
+    }
+}
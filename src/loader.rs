@@ -0,0 +1,438 @@
+use std::collections::BTreeMap;
+use std::fs::read;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use resvg::usvg::Tree;
+
+use crate::cache::{CacheKey, SpriteCache};
+use crate::composite::Composition;
+use crate::error::{LoadError, SpreetResult};
+use crate::fs::{load_svg_from_bytes, parse_svg, LoadOptions};
+use crate::source::SpriteSource;
+use crate::sprite::{sprite_name, Orientation, Overlay, RenderOptions, Sprite};
+use crate::template::{fill_placeholders, SpriteTemplate, TemplateSet};
+
+/// Builder pattern for loading SVG images from files and rendering them as [`Sprite`]s.
+///
+/// Files are parsed and rendered in parallel, using all available CPU cores unless
+/// [`SpriteLoader::jobs`] is set, but results are always returned in the same order as the input.
+#[derive(Clone)]
+#[must_use = "loader does nothing unless you call .load()"]
+pub struct SpriteLoader {
+    pixel_ratio: f32,
+    load_options: LoadOptions,
+    render_options: RenderOptions,
+    dir_render_options: Vec<(PathBuf, RenderOptions)>,
+    variants: Vec<(PathBuf, Vec<Orientation>)>,
+    sdf: bool,
+    jobs: Option<usize>,
+    cache: Option<SpriteCache>,
+}
+
+impl SpriteLoader {
+    pub fn new(pixel_ratio: f32) -> Self {
+        Self {
+            pixel_ratio,
+            load_options: LoadOptions::default(),
+            render_options: RenderOptions::default(),
+            dir_render_options: Vec::new(),
+            variants: Vec::new(),
+            sdf: false,
+            jobs: None,
+            cache: None,
+        }
+    }
+
+    /// Set the options used to parse each SVG image.
+    pub fn load_options(mut self, load_options: LoadOptions) -> Self {
+        self.load_options = load_options;
+        self
+    }
+
+    /// Set the options used to render each SVG image.
+    pub fn render_options(mut self, render_options: RenderOptions) -> Self {
+        self.render_options = render_options;
+        self
+    }
+
+    /// Set the options used to render SVG images in the directory `dir`, relative to the base path
+    /// passed to [`SpriteLoader::load`]. These override the options set with
+    /// [`SpriteLoader::render_options`], and options for more deeply nested directories take
+    /// precedence.
+    pub fn dir_render_options<P: Into<PathBuf>>(
+        mut self,
+        dir: P,
+        render_options: RenderOptions,
+    ) -> Self {
+        self.dir_render_options.push((dir.into(), render_options));
+        self
+    }
+
+    /// Also render a rotated or mirrored variant of the sprite named `name`, or of every sprite in
+    /// the directory `name`, for each of the `orientations`. Each variant is named after its
+    /// sprite and orientation, e.g. `arrow-90` or `arrow-mirror`. See [`Orientation`].
+    pub fn variants<P: Into<PathBuf>>(mut self, name: P, orientations: Vec<Orientation>) -> Self {
+        self.variants.push((name.into(), orientations));
+        self
+    }
+
+    /// Render each sprite as a signed distance field. See [`Sprite::new_sdf`].
+    pub fn make_sdf(mut self) -> Self {
+        self.sdf = true;
+        self
+    }
+
+    /// Set the number of threads used to load and render images. By default one thread is used
+    /// per CPU core.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Reuse bitmaps rendered by earlier builds from `cache`, and store newly rendered bitmaps in
+    /// it.
+    pub fn cache(mut self, cache: SpriteCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Load and render the SVG images at `paths`, naming each sprite using [`sprite_name`] relative
+    /// to `base_path`.
+    ///
+    /// Returns one result per path, in the same order as `paths`, each followed by a result for
+    /// each of its [variants](SpriteLoader::variants). A file that can't be loaded doesn't stop
+    /// the others from loading, and its error includes its path.
+    pub fn load<P, B>(&self, paths: &[P], base_path: B) -> Vec<Result<(String, Sprite), LoadError>>
+    where
+        P: AsRef<Path> + Sync,
+        B: AsRef<Path> + Sync,
+    {
+        self.load_paths(paths, base_path.as_ref(), None)
+    }
+
+    /// Load and render the SVG images at `paths` in `source`, such as a directory or an archive,
+    /// naming each sprite using [`sprite_name`]. The paths are relative to the root of the
+    /// source, as returned by [`SpriteSource::svg_paths`].
+    ///
+    /// Returns one result per path, in the same order as `paths`, each followed by a result for
+    /// each of its [variants](SpriteLoader::variants). Errors include the image's path within the
+    /// source.
+    pub fn load_source<P: AsRef<Path> + Sync>(
+        &self,
+        source: &dyn SpriteSource,
+        paths: &[P],
+    ) -> Vec<Result<(String, Sprite), LoadError>> {
+        self.load_paths(paths, Path::new("."), Some(source))
+    }
+
+    /// Generate and render one sprite for each set of values in each of the template `sets`,
+    /// naming each sprite by filling in the placeholders in the set's name pattern. Sprites are
+    /// rendered with the options set by [`SpriteLoader::render_options`].
+    ///
+    /// Returns one result per set of values, in the order given. If a template can't be read,
+    /// there's one error for the whole set. Errors include the path of the template.
+    pub fn load_templates(&self, sets: &[TemplateSet]) -> Vec<Result<(String, Sprite), LoadError>> {
+        self.install(|| {
+            sets.par_iter()
+                .flat_map(|set| {
+                    let load_error = |error| LoadError {
+                        path: set.template.clone(),
+                        error,
+                    };
+                    match SpriteTemplate::from_file(&set.template) {
+                        Ok(template) => set
+                            .value_maps()
+                            .par_iter()
+                            .map(|values| {
+                                self.load_from_template(&template, &set.name, values)
+                                    .map_err(load_error)
+                            })
+                            .collect::<Vec<_>>(),
+                        Err(error) => vec![Err(load_error(error))],
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// Render one composite sprite for each combination of values in each of the
+    /// `compositions`, naming each sprite by filling in the placeholders in the composition's name
+    /// pattern. Sprites are rendered with the options set by [`SpriteLoader::render_options`].
+    ///
+    /// Returns one result per combination, in the order given by [`Composition::combinations`].
+    /// Compositions without any layers are skipped. Errors include the path of the layer that
+    /// couldn't be loaded.
+    pub fn load_compositions(
+        &self,
+        compositions: &[Composition],
+    ) -> Vec<Result<(String, Sprite), LoadError>> {
+        self.install(|| {
+            compositions
+                .par_iter()
+                .filter(|composition| !composition.layers.is_empty())
+                .flat_map(|composition| {
+                    composition
+                        .combinations()
+                        .par_iter()
+                        .map(|values| self.load_composite(composition, values))
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        })
+    }
+
+    /// Run `op` in a thread pool with the number of threads set by [`SpriteLoader::jobs`].
+    fn install<T: Send>(&self, op: impl FnOnce() -> T + Send) -> T {
+        // If a thread pool of the right size can't be created, fall back to the global pool.
+        match self
+            .jobs
+            .map(|jobs| ThreadPoolBuilder::new().num_threads(jobs).build())
+        {
+            Some(Ok(pool)) => pool.install(op),
+            _ => op(),
+        }
+    }
+
+    /// Load and render the SVG images at `paths`, as [`SpriteLoader::load`], and collect the
+    /// sprites in a map keyed by sprite name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for every path (in the order given) that couldn't be loaded.
+    pub fn load_map<P, B>(
+        &self,
+        paths: &[P],
+        base_path: B,
+    ) -> Result<BTreeMap<String, Sprite>, Vec<LoadError>>
+    where
+        P: AsRef<Path> + Sync,
+        B: AsRef<Path> + Sync,
+    {
+        let mut sprites = BTreeMap::new();
+        let mut errors = Vec::new();
+        for result in self.load(paths, base_path) {
+            match result {
+                Ok((name, sprite)) => {
+                    sprites.insert(name, sprite);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        if errors.is_empty() {
+            Ok(sprites)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Load and render the SVG images at `paths`, and their variants, reading them from `source`
+    /// if given or from the filesystem otherwise.
+    fn load_paths<P: AsRef<Path> + Sync>(
+        &self,
+        paths: &[P],
+        base_path: &Path,
+        source: Option<&dyn SpriteSource>,
+    ) -> Vec<Result<(String, Sprite), LoadError>> {
+        let jobs = paths
+            .iter()
+            .flat_map(|path| {
+                let path = path.as_ref();
+                let orientations = self.variants_for(path, base_path);
+                std::iter::once((path, None)).chain(orientations.map(move |o| (path, Some(o))))
+            })
+            .collect::<Vec<_>>();
+        self.install(|| {
+            jobs.par_iter()
+                .map(|(path, orientation)| {
+                    self.load_one(path, base_path, *orientation, source)
+                        .map_err(|error| LoadError {
+                            path: path.to_path_buf(),
+                            error,
+                        })
+                })
+                .collect()
+        })
+    }
+
+    /// Load, render and name a single sprite, or one of its variants if `orientation` is set.
+    fn load_one(
+        &self,
+        path: &Path,
+        base_path: &Path,
+        orientation: Option<Orientation>,
+        source: Option<&dyn SpriteSource>,
+    ) -> SpreetResult<(String, Sprite)> {
+        let (data, tree) = match source {
+            Some(source) => {
+                let data = source.read(path)?;
+                let resolver = |href: &str| source.resolve(path, href);
+                let tree = load_svg_from_bytes(&data, Some(&resolver), &self.load_options)?;
+                (data, tree)
+            }
+            None => {
+                let data = read(path)?;
+                let tree = parse_svg(&data, path, &self.load_options)?;
+                (data, tree)
+            }
+        };
+        let mut options = self.render_options_for(path, base_path);
+        let mut name = sprite_name(path, base_path)?;
+        if let Some(orientation) = orientation {
+            options.orientation = orientation;
+            name = format!("{name}-{orientation}");
+        }
+        let sprite = self.render_layers(&data, &name, &options, tree, &[])?;
+        Ok((name, sprite))
+    }
+
+    /// Generate, render and name a single sprite from a template.
+    fn load_from_template(
+        &self,
+        template: &SpriteTemplate,
+        name: &str,
+        values: &BTreeMap<String, String>,
+    ) -> SpreetResult<(String, Sprite)> {
+        let name = fill_placeholders(name, values, false)?;
+        let svg = template.render_svg(values, &self.load_options)?;
+        let sprite = self.render(svg.as_bytes(), template.path(), &name, &self.render_options)?;
+        Ok((name, sprite))
+    }
+
+    /// Generate, render and name a single composite sprite from the given placeholder `values`.
+    fn load_composite(
+        &self,
+        composition: &Composition,
+        values: &BTreeMap<String, String>,
+    ) -> Result<(String, Sprite), LoadError> {
+        let mut data = Vec::new();
+        let mut layers = Vec::with_capacity(composition.layers.len());
+        for layer in &composition.layers {
+            let path = PathBuf::from(&layer.source);
+            let load_error = |error| LoadError {
+                path: path.clone(),
+                error,
+            };
+            let path = fill_placeholders(&layer.source, values, false)
+                .map(PathBuf::from)
+                .map_err(load_error)?;
+            let load_error = |error| LoadError {
+                path: path.clone(),
+                error,
+            };
+            let svg = SpriteTemplate::from_file(&path)
+                .and_then(|template| template.render_svg(values, &self.load_options))
+                .map_err(load_error)?;
+            let tree = parse_svg(svg.as_bytes(), &path, &self.load_options).map_err(load_error)?;
+            // The cache key covers every layer's SVG and where it's drawn.
+            data.extend(svg.len().to_le_bytes());
+            data.extend(svg.as_bytes());
+            data.extend(layer.offset[0].to_le_bytes());
+            data.extend(layer.offset[1].to_le_bytes());
+            data.extend(layer.scale.to_le_bytes());
+            layers.push((
+                path,
+                Overlay {
+                    tree,
+                    x: layer.offset[0],
+                    y: layer.offset[1],
+                    scale: layer.scale,
+                },
+            ));
+        }
+        let mut layers = layers.into_iter();
+        let Some((path, base)) = layers.next() else {
+            unreachable!("compositions without layers are skipped");
+        };
+        let overlays = layers.map(|(_, overlay)| overlay).collect::<Vec<_>>();
+        let load_error = |error| LoadError {
+            path: path.clone(),
+            error,
+        };
+        let name = fill_placeholders(&composition.name, values, false).map_err(load_error)?;
+        let sprite = self
+            .render_layers(&data, &name, &self.render_options, base.tree, &overlays)
+            .map_err(load_error)?;
+        Ok((name, sprite))
+    }
+
+    /// Parse and render SVG `data` read from `path` as the sprite `name`, using the cache if
+    /// there is one.
+    fn render(
+        &self,
+        data: &[u8],
+        path: &Path,
+        name: &str,
+        options: &RenderOptions,
+    ) -> SpreetResult<Sprite> {
+        let tree = parse_svg(data, path, &self.load_options)?;
+        self.render_layers(data, name, options, tree, &[])
+    }
+
+    /// Render a `base` SVG and any `overlays` drawn on top of it as the sprite `name`, using the
+    /// cache if there is one. `data` is the source of all the images, and is used as the cache
+    /// key.
+    fn render_layers(
+        &self,
+        data: &[u8],
+        name: &str,
+        options: &RenderOptions,
+        base: Tree,
+        overlays: &[Overlay],
+    ) -> SpreetResult<Sprite> {
+        let key = self.cache.as_ref().map(|_| {
+            CacheKey::new(
+                data,
+                self.pixel_ratio,
+                &self.load_options,
+                options,
+                self.sdf,
+            )
+        });
+        let cached = self
+            .cache
+            .as_ref()
+            .zip(key)
+            .and_then(|(c, key)| c.get(&key));
+        let hit = cached.is_some();
+        let sprite = match cached {
+            Some(pixmap) => Sprite::from_pixmap(base, self.pixel_ratio, options, pixmap),
+            None if self.sdf => {
+                Sprite::new_sdf_composite(base, overlays, self.pixel_ratio, options)
+            }
+            None => Sprite::new_composite(base, overlays, self.pixel_ratio, options),
+        }
+        .map_err(|error| error.for_sprite(name))?;
+        if let (Some(cache), Some(key), false) = (&self.cache, key, hit) {
+            cache.put(&key, sprite.pixmap())?;
+        }
+        Ok(sprite)
+    }
+
+    /// Returns the render options for the SVG at `path`.
+    fn render_options_for(&self, path: &Path, base_path: &Path) -> RenderOptions {
+        let rel_path = path.strip_prefix(base_path).unwrap_or(path);
+        self.dir_render_options
+            .iter()
+            .filter(|(dir, _)| rel_path.starts_with(dir))
+            .max_by_key(|(dir, _)| dir.components().count())
+            .map_or(self.render_options, |(_, options)| *options)
+    }
+
+    /// Returns the orientations of the variants to render for the SVG at `path`.
+    fn variants_for<'a>(
+        &'a self,
+        path: &Path,
+        base_path: &Path,
+    ) -> impl Iterator<Item = Orientation> + 'a {
+        let name = sprite_name(path, base_path).ok();
+        self.variants
+            .iter()
+            .filter(move |(rule, _)| {
+                name.as_deref()
+                    .is_some_and(|name| Path::new(name).starts_with(rule))
+            })
+            .flat_map(|(_, orientations)| orientations.iter().copied())
+    }
+}
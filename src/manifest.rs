@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+use std::fs::read_to_string;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{LoadError, SpreetError, SpreetResult};
+
+/// Parse a manifest: a list of the icons to include in a spritesheet.
+///
+/// A manifest is either a JSON array of strings, or plain text with one icon per line. In plain
+/// text, blank lines and lines starting with `#` are ignored. Each icon is a sprite name (e.g.
+/// `transport/bus`) or a path to an SVG file (e.g. `transport/bus.svg`). See
+/// [`resolve_manifest`].
+///
+/// # Errors
+///
+/// Returns an error if the manifest looks like JSON but isn't an array of strings.
+pub fn parse_manifest(text: &str) -> SpreetResult<Vec<String>> {
+    if text.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(text)?);
+    }
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// Read a manifest from a file. See [`parse_manifest`].
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or isn't a valid manifest.
+pub fn load_manifest<P: AsRef<Path>>(path: P) -> SpreetResult<Vec<String>> {
+    parse_manifest(&read_to_string(path)?)
+}
+
+/// Find the SVG file for each icon listed in a manifest, in the directory `base_path`.
+///
+/// An icon can be a sprite name, which is the file's path relative to `base_path` without the
+/// `.svg` or `.svgz` extension (the same rules as [`sprite_name`](crate::sprite_name)), or a path
+/// to the file. Paths are relative to `base_path`, or to the current directory (or absolute) if
+/// they lead to a file inside `base_path`; icons outside `base_path` can't be used. Returns the
+/// paths, in `base_path`, in the order they're listed, without duplicates.
+///
+/// # Errors
+///
+/// Returns an error for every icon that has no SVG file, with the path where it was expected.
+pub fn resolve_manifest<P: AsRef<Path>>(
+    icons: &[String],
+    base_path: P,
+) -> Result<Vec<PathBuf>, Vec<LoadError>> {
+    let base_path = base_path.as_ref();
+    let mut seen = HashSet::new();
+    let mut paths = Vec::new();
+    let mut errors = Vec::new();
+    for icon in icons {
+        match find_icon(icon, base_path) {
+            Some(path) => {
+                if seen.insert(path.clone()) {
+                    paths.push(path);
+                }
+            }
+            None => errors.push(LoadError {
+                path: base_path.join(icon),
+                error: SpreetError::MissingIcon(icon.clone()),
+            }),
+        }
+    }
+    if errors.is_empty() {
+        Ok(paths)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Returns the path of the SVG file for `icon` in `base_path`, if there is one.
+fn find_icon(icon: &str, base_path: &Path) -> Option<PathBuf> {
+    let path = Path::new(icon);
+    // Icons must be inside the base directory.
+    if path.components().any(|c| c == Component::ParentDir) {
+        return None;
+    }
+    let candidates = if path.extension().is_some_and(|s| s == "svg" || s == "svgz") {
+        vec![base_path.join(path), path.to_path_buf()]
+    } else {
+        vec![
+            base_path.join(format!("{icon}.svg")),
+            base_path.join(format!("{icon}.svgz")),
+        ]
+    };
+    // Compare canonical paths, so that a path given as `./icons/bus.svg`, or as an absolute path,
+    // is still found in `icons`. The path returned is always in `base_path`, so that the sprite is
+    // named relative to it.
+    let canonical_base = base_path.canonicalize().ok()?;
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.is_file())
+        .find_map(|candidate| {
+            let canonical = candidate.canonicalize().ok()?;
+            let relative = canonical.strip_prefix(&canonical_base).ok()?;
+            Some(base_path.join(relative))
+        })
+}
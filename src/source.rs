@@ -0,0 +1,344 @@
+#[cfg(any(feature = "zip", feature = "tar"))]
+use std::collections::BTreeMap;
+use std::fs::read;
+#[cfg(any(feature = "zip", feature = "tar"))]
+use std::fs::File;
+#[cfg(any(feature = "zip", feature = "tar"))]
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+#[cfg(any(feature = "zip", feature = "tar"))]
+use crate::error::SpreetError;
+use crate::error::SpreetResult;
+#[cfg(any(feature = "zip", feature = "tar"))]
+use crate::fs::IGNORE_FILE_NAME;
+use crate::fs::{
+    filter_relative_paths, get_svg_input_paths, get_svg_input_paths_with_filter, InputFilter,
+};
+
+/// A collection of SVG images that sprites can be built from, such as a directory or an archive.
+///
+/// Images are identified by their paths relative to the root of the source, which are also used
+/// to name the sprites (see [`sprite_name`](crate::sprite_name)). Use
+/// [`SpriteLoader::load_source`](crate::SpriteLoader::load_source) to render the images in a
+/// source.
+pub trait SpriteSource: Send + Sync {
+    /// Returns the paths of the SVG and SVGZ images in the source, relative to its root. Hidden
+    /// files (whose names begin with `.`) are left out. If `recursive` is `true` it will also
+    /// return images in sub-directories.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source can't be read.
+    fn svg_paths(&self, recursive: bool) -> SpreetResult<Vec<PathBuf>>;
+
+    /// Returns the paths of the SVG and SVGZ images in the source that pass `filter`, like
+    /// [`svg_paths`](Self::svg_paths).
+    ///
+    /// By default, the patterns in `filter` are matched against the paths relative to the root of
+    /// the source, and `.spreetignore` files are read from the source with
+    /// [`read`](Self::read).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source can't be read, or if a pattern or `.spreetignore` file is
+    /// invalid.
+    fn svg_paths_with_filter(
+        &self,
+        recursive: bool,
+        filter: &InputFilter,
+    ) -> SpreetResult<Vec<PathBuf>> {
+        filter_relative_paths(self.svg_paths(recursive)?, filter, |path| {
+            self.read(path).ok()
+        })
+    }
+
+    /// Returns the contents of the file at `path`, relative to the root of the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no such file or it can't be read.
+    fn read(&self, path: &Path) -> SpreetResult<Vec<u8>>;
+
+    /// Returns the data for a resource referred to by the SVG image at `path`, such as the `href`
+    /// of an `<image>` element, or `None` if it can't be found.
+    ///
+    /// By default, `href` is treated as a path relative to the image and read from the source.
+    /// URLs and paths that lead outside the source aren't resolved.
+    fn resolve(&self, path: &Path, href: &str) -> Option<Vec<u8>> {
+        let target = resolve_relative(path, href)?;
+        self.read(&target).ok()
+    }
+}
+
+/// A directory of SVG images.
+#[derive(Clone, Debug)]
+pub struct DirSource {
+    root: PathBuf,
+}
+
+impl DirSource {
+    /// Create a source for the SVG images in the directory `root`.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl SpriteSource for DirSource {
+    fn svg_paths(&self, recursive: bool) -> SpreetResult<Vec<PathBuf>> {
+        Ok(get_svg_input_paths(&self.root, recursive)?
+            .into_iter()
+            .filter_map(|path| path.strip_prefix(&self.root).ok().map(Path::to_path_buf))
+            .collect())
+    }
+
+    fn svg_paths_with_filter(
+        &self,
+        recursive: bool,
+        filter: &InputFilter,
+    ) -> SpreetResult<Vec<PathBuf>> {
+        Ok(
+            get_svg_input_paths_with_filter(&self.root, recursive, filter)?
+                .into_iter()
+                .filter_map(|path| path.strip_prefix(&self.root).ok().map(Path::to_path_buf))
+                .collect(),
+        )
+    }
+
+    fn read(&self, path: &Path) -> SpreetResult<Vec<u8>> {
+        Ok(read(self.root.join(path))?)
+    }
+}
+
+/// SVG images read from an archive, such as an icon pack delivered as a zip file.
+///
+/// The archive's images (and any `.spreetignore` files) are read into memory when the source is
+/// created, so that they can be rendered in parallel. Other files, such as fonts and previews,
+/// are skipped, and [`ArchiveOptions::max_file_size`] limits the size of each image.
+#[cfg(any(feature = "zip", feature = "tar"))]
+#[derive(Clone, Debug)]
+pub struct ArchiveSource {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+/// Options used to read an [`ArchiveSource`].
+#[cfg(any(feature = "zip", feature = "tar"))]
+#[derive(Clone, Debug)]
+pub struct ArchiveOptions {
+    /// The size, in bytes, of the largest file that can be read from an archive. Only SVG images,
+    /// the images they can refer to, and `.spreetignore` files are read, and an archive with a
+    /// larger one can't be opened. The default is 16 MiB.
+    pub max_file_size: u64,
+}
+
+#[cfg(any(feature = "zip", feature = "tar"))]
+impl Default for ArchiveOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// Extensions of the files read from an archive: SVG images, and the raster images they can refer
+/// to.
+#[cfg(any(feature = "zip", feature = "tar"))]
+const ARCHIVE_EXTENSIONS: [&str; 7] = ["svg", "svgz", "png", "jpg", "jpeg", "gif", "webp"];
+
+#[cfg(any(feature = "zip", feature = "tar"))]
+impl ArchiveSource {
+    /// Open the archive at `path`, choosing its format from the file extension: `.zip`, `.tar`,
+    /// `.tar.gz` or `.tgz`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read, isn't a valid archive, has an extension that
+    /// isn't supported, or has a file larger than the default
+    /// [`max_file_size`](ArchiveOptions::max_file_size).
+    pub fn open<P: AsRef<Path>>(path: P) -> SpreetResult<Self> {
+        Self::open_with_options(path, &ArchiveOptions::default())
+    }
+
+    /// Open the archive at `path` with `options`. See [`Self::open`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read, isn't a valid archive, has an extension that
+    /// isn't supported, or has a file larger than `options.max_file_size`.
+    pub fn open_with_options<P: AsRef<Path>>(
+        path: P,
+        options: &ArchiveOptions,
+    ) -> SpreetResult<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let file = File::open(path)?;
+        match name {
+            #[cfg(feature = "zip")]
+            _ if name.ends_with(".zip") => Self::from_zip(file, options),
+            #[cfg(feature = "tar")]
+            _ if name.ends_with(".tar.gz") || name.ends_with(".tgz") => {
+                Self::from_tar_gz(file, options)
+            }
+            #[cfg(feature = "tar")]
+            _ if name.ends_with(".tar") => Self::from_tar(file, options),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("unsupported archive format {path:?}"),
+            )
+            .into()),
+        }
+    }
+
+    /// Read a zip archive. Only SVG images, the images they can refer to, and `.spreetignore`
+    /// files are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive can't be read, or if one of the files it keeps is larger
+    /// than `options.max_file_size`.
+    #[cfg(feature = "zip")]
+    pub fn from_zip<R: Read + io::Seek>(reader: R, options: &ArchiveOptions) -> SpreetResult<Self> {
+        let mut archive = zip::ZipArchive::new(reader)?;
+        let mut files = BTreeMap::new();
+        for i in 0..archive.len() {
+            let file = archive.by_index(i)?;
+            // Skip directories, and entries whose names would escape the archive.
+            let Some(path) = file.enclosed_name().filter(|_| file.is_file()) else {
+                continue;
+            };
+            if is_archive_file(&path) {
+                let data = read_archive_file(file, &path, options)?;
+                files.insert(path, data);
+            }
+        }
+        Ok(Self { files })
+    }
+
+    /// Read a tar archive. Only SVG images, the images they can refer to, and `.spreetignore`
+    /// files are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive can't be read, or if one of the files it keeps is larger
+    /// than `options.max_file_size`.
+    #[cfg(feature = "tar")]
+    pub fn from_tar<R: Read>(reader: R, options: &ArchiveOptions) -> SpreetResult<Self> {
+        let mut archive = tar::Archive::new(reader);
+        let mut files = BTreeMap::new();
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            // Skip entries whose names would escape the archive.
+            let Some(path) = resolve_relative(Path::new(""), &entry.path()?.to_string_lossy())
+            else {
+                continue;
+            };
+            if is_archive_file(&path) {
+                let data = read_archive_file(entry, &path, options)?;
+                files.insert(path, data);
+            }
+        }
+        Ok(Self { files })
+    }
+
+    /// Read a tar archive compressed with gzip. See [`Self::from_tar`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive can't be read, or if one of the files it keeps is larger
+    /// than `options.max_file_size`.
+    #[cfg(feature = "tar")]
+    pub fn from_tar_gz<R: Read>(reader: R, options: &ArchiveOptions) -> SpreetResult<Self> {
+        Self::from_tar(flate2::read::GzDecoder::new(reader), options)
+    }
+}
+
+/// Returns `true` if the file at `path` in an archive could be needed to render its SVG images.
+#[cfg(any(feature = "zip", feature = "tar"))]
+fn is_archive_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == IGNORE_FILE_NAME)
+        || path.extension().is_some_and(|extension| {
+            let extension = extension.to_string_lossy().to_lowercase();
+            ARCHIVE_EXTENSIONS.contains(&extension.as_str())
+        })
+}
+
+/// Read the file at `path` in an archive from `reader`, unless it's larger than
+/// `options.max_file_size`.
+#[cfg(any(feature = "zip", feature = "tar"))]
+fn read_archive_file<R: Read>(
+    reader: R,
+    path: &Path,
+    options: &ArchiveOptions,
+) -> SpreetResult<Vec<u8>> {
+    // The sizes recorded in an archive can't be trusted, so stop reading just past the limit.
+    let mut data = Vec::new();
+    reader
+        .take(options.max_file_size.saturating_add(1))
+        .read_to_end(&mut data)?;
+    if data.len() as u64 > options.max_file_size {
+        return Err(SpreetError::ArchiveFileTooLarge {
+            path: path.to_path_buf(),
+            max_size: options.max_file_size,
+        });
+    }
+    Ok(data)
+}
+
+#[cfg(any(feature = "zip", feature = "tar"))]
+impl SpriteSource for ArchiveSource {
+    fn svg_paths(&self, recursive: bool) -> SpreetResult<Vec<PathBuf>> {
+        Ok(self
+            .files
+            .keys()
+            .filter(|path| recursive || path.components().count() == 1)
+            .filter(|path| {
+                // Also skip files in hidden directories, like the `.git` directory.
+                !path
+                    .components()
+                    .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+            })
+            .filter(|path| path.extension().is_some_and(|s| s == "svg" || s == "svgz"))
+            .cloned()
+            .collect())
+    }
+
+    fn read(&self, path: &Path) -> SpreetResult<Vec<u8>> {
+        self.files.get(path).cloned().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("{path:?} not in archive")).into()
+        })
+    }
+}
+
+/// Returns the path of `href`, relative to the file at `path`, as a normalised relative path.
+/// Returns `None` if `href` is a URL or an absolute path, or leads outside the root.
+fn resolve_relative(path: &Path, href: &str) -> Option<PathBuf> {
+    // `C:` is a Windows drive, but anything longer before a colon is a URL scheme.
+    if href
+        .split_once(':')
+        .is_some_and(|(scheme, _)| scheme.len() > 1)
+    {
+        return None;
+    }
+    let mut resolved = PathBuf::new();
+    let parent = path.parent().unwrap_or(Path::new(""));
+    for component in parent.components().chain(Path::new(href).components()) {
+        match component {
+            Component::Normal(name) => resolved.push(name),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(resolved)
+}
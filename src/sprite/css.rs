@@ -0,0 +1,189 @@
+use std::fmt::Write;
+
+use crate::sprite::{SpriteDescription, Spritesheet};
+
+/// A spritesheet to use in a CSS stylesheet, and the URL its image will be served from.
+pub struct CssImage<'a> {
+    /// The spritesheet, whose index gives the size and position of each sprite.
+    pub spritesheet: &'a Spritesheet,
+    /// URL of the spritesheet's image, relative to the stylesheet (e.g. `sprite@2x.png`).
+    pub url: String,
+}
+
+/// Options used to write a CSS stylesheet with [`spritesheet_css`].
+#[derive(Clone, Debug)]
+pub struct CssOptions {
+    /// Prefix added to each sprite name to make its class name.
+    pub class_prefix: String,
+}
+
+impl Default for CssOptions {
+    fn default() -> Self {
+        Self {
+            class_prefix: "sprite-".to_string(),
+        }
+    }
+}
+
+/// Write a CSS stylesheet with a class for each sprite, so that a spritesheet built for a map can
+/// also be used for icons in a web page.
+///
+/// Each class sets an element's `width` and `height` to the size of the sprite, and shows the
+/// sprite as its background. Sizes and positions are in CSS pixels, so a spritesheet with a pixel
+/// ratio of 2 shows its sprites at half their size in image pixels. Class names are the sprite
+/// names, with `options.class_prefix` in front and any characters that aren't allowed in a CSS
+/// identifier escaped (e.g. `.sprite-transport\/bus`).
+///
+/// `images` can be the same sprites rendered at different pixel ratios (e.g. `sprite.png` and
+/// `sprite@2x.png`). The one with the lowest pixel ratio is used for the positions of the sprites
+/// and as a fallback. The browser chooses between the others with `image-set()`, as long as
+/// their sprites are in the same places, scaled by their pixel ratio. Spritesheets whose sprites
+/// were packed differently are given rules of their own, in a `min-resolution` media query.
+/// Sprites that are missing from the lowest pixel ratio image are left out.
+pub fn spritesheet_css(images: &[CssImage], options: &CssOptions) -> String {
+    let mut images = images.iter().collect::<Vec<_>>();
+    images.sort_by(|a, b| pixel_ratio(a.spritesheet).total_cmp(&pixel_ratio(b.spritesheet)));
+    let Some((base, others)) = images.split_first() else {
+        return String::new();
+    };
+    let (scaled, packed_differently): (Vec<&CssImage>, Vec<&CssImage>) = others
+        .iter()
+        .partition(|image| has_same_layout(base.spritesheet, image.spritesheet));
+
+    let mut css = String::new();
+    for (name, sprite) in base.spritesheet.get_index() {
+        let selector = class_selector(&options.class_prefix, name);
+        let ratio = sprite.pixel_ratio;
+        writeln!(css, "{selector} {{").unwrap();
+        writeln!(css, "  width: {};", css_px(sprite.width as f32 / ratio)).unwrap();
+        writeln!(css, "  height: {};", css_px(sprite.height as f32 / ratio)).unwrap();
+        writeln!(css, "  background-image: url({});", css_string(&base.url)).unwrap();
+        if !scaled.is_empty() {
+            let image_set = std::iter::once(*base)
+                .chain(scaled.iter().copied())
+                .map(|image| {
+                    let ratio = format_number(pixel_ratio(image.spritesheet));
+                    format!("url({}) {ratio}x", css_string(&image.url))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(css, "  background-image: image-set({image_set});").unwrap();
+        }
+        write_background(&mut css, base.spritesheet, sprite);
+        writeln!(css, "}}").unwrap();
+    }
+    for image in packed_differently {
+        let ratio = format_number(pixel_ratio(image.spritesheet));
+        writeln!(css, "\n@media (min-resolution: {ratio}dppx) {{").unwrap();
+        for (name, sprite) in image.spritesheet.get_index() {
+            if !base.spritesheet.get_index().contains_key(name) {
+                continue;
+            }
+            let selector = class_selector(&options.class_prefix, name);
+            writeln!(css, "  {selector} {{").unwrap();
+            writeln!(
+                css,
+                "    background-image: url({});",
+                css_string(&image.url)
+            )
+            .unwrap();
+            let mut background = String::new();
+            write_background(&mut background, image.spritesheet, sprite);
+            for line in background.lines() {
+                writeln!(css, "  {line}").unwrap();
+            }
+            writeln!(css, "  }}").unwrap();
+        }
+        writeln!(css, "}}").unwrap();
+    }
+    css
+}
+
+/// Write the declarations that show `sprite`, from `spritesheet`, as an element's background.
+fn write_background(css: &mut String, spritesheet: &Spritesheet, sprite: &SpriteDescription) {
+    let ratio = sprite.pixel_ratio;
+    writeln!(css, "  background-repeat: no-repeat;").unwrap();
+    writeln!(
+        css,
+        "  background-position: {} {};",
+        css_px(-(sprite.x as f32) / ratio),
+        css_px(-(sprite.y as f32) / ratio),
+    )
+    .unwrap();
+    writeln!(
+        css,
+        "  background-size: {} {};",
+        css_px(spritesheet.width() as f32 / ratio),
+        css_px(spritesheet.height() as f32 / ratio),
+    )
+    .unwrap();
+}
+
+/// Returns the pixel ratio of the sprites in `spritesheet`.
+fn pixel_ratio(spritesheet: &Spritesheet) -> f32 {
+    spritesheet
+        .get_index()
+        .values()
+        .next()
+        .map_or(1.0, |sprite| sprite.pixel_ratio)
+}
+
+/// Returns `true` if every sprite in `base` is in the same place in `other`, and the same size,
+/// once scaled by their pixel ratios.
+fn has_same_layout(base: &Spritesheet, other: &Spritesheet) -> bool {
+    let scale = pixel_ratio(other) / pixel_ratio(base);
+    let same = |a: u32, b: u32| (a as f32 * scale - b as f32).abs() <= 0.5;
+    same(base.width(), other.width())
+        && same(base.height(), other.height())
+        && base.get_index().iter().all(|(name, a)| {
+            other.get_index().get(name).is_some_and(|b| {
+                same(a.x, b.x)
+                    && same(a.y, b.y)
+                    && same(a.width, b.width)
+                    && same(a.height, b.height)
+            })
+        })
+}
+
+/// Returns a CSS class selector for the sprite `name`, escaping any characters that aren't
+/// allowed in an identifier.
+fn class_selector(prefix: &str, name: &str) -> String {
+    let mut selector = String::from(".");
+    for (i, c) in prefix.chars().chain(name.chars()).enumerate() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '_' | '-' => selector.push(c),
+            '0'..='9' if i > 0 => selector.push(c),
+            // An identifier can't start with a digit, which must be escaped as a code point.
+            '0'..='9' => write!(selector, "\\{:x} ", c as u32).unwrap(),
+            _ if !c.is_ascii() => selector.push(c),
+            _ => {
+                selector.push('\\');
+                selector.push(c);
+            }
+        }
+    }
+    selector
+}
+
+/// Returns `value` as a quoted CSS string.
+fn css_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Returns a length of `value` CSS pixels.
+fn css_px(value: f32) -> String {
+    if value == 0.0 {
+        "0".to_string()
+    } else {
+        format!("{}px", format_number(value))
+    }
+}
+
+/// Returns `value` with at most three decimal places, and without trailing zeros.
+fn format_number(value: f32) -> String {
+    let formatted = format!("{value:.3}");
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
@@ -0,0 +1,226 @@
+use oxipng::{optimize_from_memory, Deflater, FilterStrategy, StripChunks, ZopfliOptions};
+#[cfg(any(feature = "webp", feature = "avif"))]
+use resvg::tiny_skia::Pixmap;
+
+#[cfg(feature = "webp")]
+use crate::error::SpreetError;
+use crate::error::SpreetResult;
+use crate::sprite::QuantizationReport;
+
+/// The image format used to encode a spritesheet.
+///
+/// MapLibre and Mapbox can load a spritesheet in any image format the browser can decode. PNG is
+/// supported everywhere, but WebP and AVIF are usually much smaller. WebP and AVIF are only
+/// available with the `webp` and `avif` Cargo features, so the enum is non-exhaustive.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub enum ImageFormat {
+    /// PNG, optimised using [`oxipng`](https://github.com/shssoichiro/oxipng).
+    #[default]
+    Png,
+    /// Lossless WebP.
+    #[cfg(feature = "webp")]
+    LosslessWebp,
+    /// Lossy WebP, with a quality from 0 (smallest file) to 100 (best quality).
+    #[cfg(feature = "webp")]
+    Webp { quality: f32 },
+    /// AVIF, with a quality from 1 (smallest file) to 100 (best quality).
+    #[cfg(feature = "avif")]
+    Avif { quality: f32 },
+}
+
+impl ImageFormat {
+    /// The file extension (without a leading `.`) used for images in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            #[cfg(feature = "webp")]
+            ImageFormat::LosslessWebp | ImageFormat::Webp { .. } => "webp",
+            #[cfg(feature = "avif")]
+            ImageFormat::Avif { .. } => "avif",
+        }
+    }
+}
+
+/// Returns the pixels of `pixmap` as non-premultiplied RGBA bytes.
+#[cfg(any(feature = "webp", feature = "avif"))]
+fn rgba_bytes(pixmap: &Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+/// Encode `pixmap` as a WebP image, either losslessly or with a lossy `quality` from 0 to 100.
+#[cfg(feature = "webp")]
+pub(crate) fn encode_webp(pixmap: &Pixmap, quality: Option<f32>) -> SpreetResult<Vec<u8>> {
+    let data = rgba_bytes(pixmap);
+    let encoder = webp::Encoder::from_rgba(&data, pixmap.width(), pixmap.height());
+    let mut config =
+        webp::WebPConfig::new().map_err(|_| SpreetError::WebpError(String::from("bad config")))?;
+    match quality {
+        Some(quality) => config.quality = quality.clamp(0.0, 100.0),
+        None => {
+            // In lossless mode the quality is the effort spent compressing the image.
+            config.lossless = 1;
+            config.quality = 100.0;
+            config.method = 6;
+        }
+    }
+    let webp = encoder
+        .encode_advanced(&config)
+        .map_err(|e| SpreetError::WebpError(format!("{e:?}")))?;
+    Ok(webp.to_vec())
+}
+
+/// Encode `pixmap` as an AVIF image with a `quality` from 1 to 100.
+#[cfg(feature = "avif")]
+pub(crate) fn encode_avif(pixmap: &Pixmap, quality: f32) -> SpreetResult<Vec<u8>> {
+    let data = rgba_bytes(pixmap);
+    let pixels = data
+        .chunks_exact(4)
+        .map(|c| ravif::RGBA8::new(c[0], c[1], c[2], c[3]))
+        .collect::<Vec<_>>();
+    let image = ravif::Img::new(
+        pixels.as_slice(),
+        pixmap.width() as usize,
+        pixmap.height() as usize,
+    );
+    let quality = quality.clamp(1.0, 100.0);
+    let encoded = ravif::Encoder::new()
+        .with_quality(quality)
+        .with_alpha_quality(quality)
+        .encode_rgba(image)?;
+    Ok(encoded.avif_file)
+}
+
+/// A strategy used by oxipng to choose the filter applied to each row of a PNG image before it's
+/// compressed. See the [oxipng documentation] for details.
+///
+/// [oxipng documentation]: https://docs.rs/oxipng/latest/oxipng/enum.FilterStrategy.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PngFilter {
+    None,
+    Sub,
+    Up,
+    Average,
+    Paeth,
+    MinSum,
+    Entropy,
+    Bigrams,
+    BigEnt,
+    Brute,
+}
+
+impl From<PngFilter> for FilterStrategy {
+    fn from(filter: PngFilter) -> Self {
+        match filter {
+            PngFilter::None => FilterStrategy::NONE,
+            PngFilter::Sub => FilterStrategy::SUB,
+            PngFilter::Up => FilterStrategy::UP,
+            PngFilter::Average => FilterStrategy::AVERAGE,
+            PngFilter::Paeth => FilterStrategy::PAETH,
+            PngFilter::MinSum => FilterStrategy::MinSum,
+            PngFilter::Entropy => FilterStrategy::Entropy,
+            PngFilter::Bigrams => FilterStrategy::Bigrams,
+            PngFilter::BigEnt => FilterStrategy::BigEnt,
+            PngFilter::Brute => FilterStrategy::Brute {
+                num_lines: 4,
+                level: 4,
+            },
+        }
+    }
+}
+
+/// Options that control how a PNG spritesheet is optimised by [oxipng].
+///
+/// The [`Default`] options are a good balance between speed and file size. Use
+/// [`PngOptions::fast`] to skip optimisation entirely (e.g. for development builds), or
+/// [`PngOptions::max`] to spend much longer making the smallest possible file (e.g. for releases).
+///
+/// [oxipng]: https://github.com/shssoichiro/oxipng
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PngOptions {
+    /// oxipng optimisation level, from 0 (fastest) to 6 (smallest file), or `None` to save the PNG
+    /// without optimising it.
+    pub level: Option<u8>,
+    /// Filters to try on each row of the image. The filter that compresses best is used. If empty,
+    /// the filters chosen by the optimisation level are used.
+    pub filters: Vec<PngFilter>,
+    /// Compress the image using Zopfli, which is much slower but produces smaller files.
+    pub zopfli: bool,
+    /// Remove metadata chunks that don't affect how the image is displayed.
+    pub strip_metadata: bool,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            level: Some(2),
+            filters: vec![PngFilter::None],
+            zopfli: false,
+            strip_metadata: false,
+        }
+    }
+}
+
+impl PngOptions {
+    /// Options that skip optimisation entirely.
+    pub fn fast() -> Self {
+        Self {
+            level: None,
+            ..Self::default()
+        }
+    }
+
+    /// Options that make the smallest possible file, at the cost of speed.
+    pub fn max() -> Self {
+        Self {
+            level: Some(6),
+            filters: Vec::new(),
+            zopfli: true,
+            strip_metadata: true,
+        }
+    }
+
+    /// Convert to oxipng's options, or `None` if the PNG shouldn't be optimised.
+    fn to_oxipng(&self) -> Option<oxipng::Options> {
+        let mut options = oxipng::Options::from_preset(self.level?);
+        if !self.filters.is_empty() {
+            options.filters = self.filters.iter().map(|f| (*f).into()).collect();
+        }
+        if self.zopfli {
+            options.deflater = Deflater::Zopfli(ZopfliOptions::default());
+        }
+        if self.strip_metadata {
+            options.strip = StripChunks::Safe;
+        }
+        Some(options)
+    }
+}
+
+/// Details of how a spritesheet was encoded as a PNG, returned by
+/// [`Spritesheet::encode_png_with_report`](crate::Spritesheet::encode_png_with_report).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PngReport {
+    /// Size in bytes of the full-colour PNG before it was optimised.
+    pub unoptimized_size: usize,
+    /// Size in bytes of the encoded PNG.
+    pub size: usize,
+    /// The outcome of quantising the spritesheet, or `None` if it wasn't quantised.
+    pub quantization: Option<QuantizationReport>,
+}
+
+/// Optimise an in-memory PNG image using the [`oxipng`] library.
+///
+/// [`oxipng`]: https://github.com/shssoichiro/oxipng
+pub(crate) fn optimize_png(data: Vec<u8>, options: &PngOptions) -> SpreetResult<Vec<u8>> {
+    match options.to_oxipng() {
+        Some(options) => Ok(optimize_from_memory(&data, &options)?),
+        None => Ok(data),
+    }
+}
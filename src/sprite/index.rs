@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::sprite::SpriteDescription;
+
+/// The format of a spritesheet's index file, which describes where each sprite is in the
+/// spritesheet.
+///
+/// The [MapLibre/Mapbox] format is the one map styles need, but the same spritesheet can be used
+/// by other renderers if its index is saved in their format.
+///
+/// [MapLibre/Mapbox]: https://maplibre.org/maplibre-style-spec/sprite/#index-file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IndexFormat {
+    /// A MapLibre or Mapbox style sprite index, as saved by
+    /// [`Spritesheet::save_index`](super::Spritesheet::save_index).
+    #[default]
+    MapLibre,
+    /// An icon mapping for a [deck.gl `IconLayer`](https://deck.gl/docs/api-reference/layers/icon-layer),
+    /// with SDF sprites marked as masks so that they can be tinted.
+    DeckGl,
+    /// TexturePacker's JSON (hash) format, an object of frames keyed by name, as read by PixiJS
+    /// and Phaser.
+    TexturePackerHash,
+    /// TexturePacker's JSON (array) format, a list of frames each with a `filename`.
+    TexturePackerArray,
+    /// A generic XML texture atlas (in the Sparrow/Starling format), with a `<SubTexture>` element
+    /// for each sprite.
+    Xml,
+}
+
+impl IndexFormat {
+    /// The file extension (without a leading `.`) used for index files in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            IndexFormat::Xml => "xml",
+            _ => "json",
+        }
+    }
+}
+
+/// A sprite in a deck.gl icon mapping.
+#[derive(Serialize)]
+struct DeckGlIcon {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    mask: bool,
+}
+
+impl From<&SpriteDescription> for DeckGlIcon {
+    fn from(sprite: &SpriteDescription) -> Self {
+        Self {
+            x: sprite.x,
+            y: sprite.y,
+            width: sprite.width,
+            height: sprite.height,
+            mask: sprite.sdf,
+        }
+    }
+}
+
+/// The size of a frame or an image in a TexturePacker index.
+#[derive(Serialize)]
+struct TexturePackerSize {
+    w: u32,
+    h: u32,
+}
+
+/// A frame's rectangle in a TexturePacker index.
+#[derive(Serialize)]
+struct TexturePackerRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+/// A sprite in a TexturePacker index. Spreet never rotates or trims sprites, so each frame is
+/// its whole source image.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TexturePackerFrame<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<&'a str>,
+    frame: TexturePackerRect,
+    rotated: bool,
+    trimmed: bool,
+    sprite_source_size: TexturePackerRect,
+    source_size: TexturePackerSize,
+}
+
+impl<'a> TexturePackerFrame<'a> {
+    fn new(filename: Option<&'a str>, sprite: &SpriteDescription) -> Self {
+        Self {
+            filename,
+            frame: TexturePackerRect {
+                x: sprite.x,
+                y: sprite.y,
+                w: sprite.width,
+                h: sprite.height,
+            },
+            rotated: false,
+            trimmed: false,
+            sprite_source_size: TexturePackerRect {
+                x: 0,
+                y: 0,
+                w: sprite.width,
+                h: sprite.height,
+            },
+            source_size: TexturePackerSize {
+                w: sprite.width,
+                h: sprite.height,
+            },
+        }
+    }
+}
+
+/// Information about the spritesheet image in a TexturePacker index.
+#[derive(Serialize)]
+struct TexturePackerMeta<'a> {
+    app: &'static str,
+    version: &'static str,
+    image: &'a str,
+    format: &'static str,
+    size: TexturePackerSize,
+    scale: String,
+}
+
+/// A TexturePacker index, whose frames are either an object or an array.
+#[derive(Serialize)]
+struct TexturePackerIndex<'a, F> {
+    frames: F,
+    meta: TexturePackerMeta<'a>,
+}
+
+/// The spritesheet an index describes: its image's file name and size in pixels.
+pub(crate) struct IndexImage<'a> {
+    pub(crate) path: &'a str,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+/// Returns `index` encoded in `format`. `image` is used by formats that refer to the spritesheet.
+pub(crate) fn encode_index(
+    index: &BTreeMap<String, SpriteDescription>,
+    format: IndexFormat,
+    image: &IndexImage,
+    minify: bool,
+) -> serde_json::Result<String> {
+    match format {
+        IndexFormat::MapLibre => to_json(&index, minify),
+        IndexFormat::DeckGl => {
+            let icons = index
+                .iter()
+                .map(|(name, sprite)| (name, DeckGlIcon::from(sprite)))
+                .collect::<BTreeMap<_, _>>();
+            to_json(&icons, minify)
+        }
+        IndexFormat::TexturePackerHash => {
+            let frames = index
+                .iter()
+                .map(|(name, sprite)| (name, TexturePackerFrame::new(None, sprite)))
+                .collect::<BTreeMap<_, _>>();
+            to_json(&texture_packer_index(index, frames, image), minify)
+        }
+        IndexFormat::TexturePackerArray => {
+            let frames = index
+                .iter()
+                .map(|(name, sprite)| TexturePackerFrame::new(Some(name), sprite))
+                .collect::<Vec<_>>();
+            to_json(&texture_packer_index(index, frames, image), minify)
+        }
+        IndexFormat::Xml => Ok(xml_atlas(index, image, minify)),
+    }
+}
+
+/// Returns `value` as JSON, without whitespace if `minify` is `true`.
+fn to_json<T: Serialize>(value: &T, minify: bool) -> serde_json::Result<String> {
+    if minify {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    }
+}
+
+/// Returns a TexturePacker index with `frames`.
+fn texture_packer_index<'a, F>(
+    index: &BTreeMap<String, SpriteDescription>,
+    frames: F,
+    image: &IndexImage<'a>,
+) -> TexturePackerIndex<'a, F> {
+    let pixel_ratio = index
+        .values()
+        .next()
+        .map_or(1.0, |sprite| sprite.pixel_ratio);
+    TexturePackerIndex {
+        frames,
+        meta: TexturePackerMeta {
+            app: env!("CARGO_PKG_REPOSITORY"),
+            version: env!("CARGO_PKG_VERSION"),
+            image: image.path,
+            format: "RGBA8888",
+            size: TexturePackerSize {
+                w: image.width,
+                h: image.height,
+            },
+            scale: pixel_ratio.to_string(),
+        },
+    }
+}
+
+/// Returns an XML texture atlas for `index`.
+fn xml_atlas(
+    index: &BTreeMap<String, SpriteDescription>,
+    image: &IndexImage,
+    minify: bool,
+) -> String {
+    let (indent, newline) = if minify { ("", "") } else { ("  ", "\n") };
+    let mut xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>{newline}");
+    write!(
+        xml,
+        "<TextureAtlas imagePath=\"{}\" width=\"{}\" height=\"{}\">{newline}",
+        xml_escape(image.path),
+        image.width,
+        image.height,
+    )
+    .unwrap();
+    for (name, sprite) in index {
+        write!(
+            xml,
+            "{indent}<SubTexture name=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>{newline}",
+            xml_escape(name),
+            sprite.x,
+            sprite.y,
+            sprite.width,
+            sprite.height,
+        )
+        .unwrap();
+    }
+    write!(xml, "</TextureAtlas>{newline}").unwrap();
+    xml
+}
+
+/// Returns `value` escaped for use in an XML attribute.
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,4 +1,3 @@
-use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Write;
@@ -6,17 +5,33 @@ use std::path::Path;
 
 use crunch::{Item, PackedItem, PackedItems, Rotation};
 use multimap::MultiMap;
-use oxipng::indexset;
-use oxipng::optimize_from_memory;
 use resvg::tiny_skia::{Color, Pixmap, PixmapPaint, Transform};
 use resvg::usvg::{Rect, Tree};
 use sdf_glyph_renderer::{clamp_to_u8, BitmapGlyph};
 use serde::Serialize;
 
-use self::serialize::{serialize_rect, serialize_stretch_x_area, serialize_stretch_y_area};
+pub use self::css::{spritesheet_css, CssImage, CssOptions};
+use self::format::optimize_png;
+pub use self::format::{ImageFormat, PngFilter, PngOptions, PngReport};
+pub use self::index::IndexFormat;
+use self::index::{encode_index, IndexImage};
+use self::quantize::IndexedImage;
+pub use self::quantize::QuantizationReport;
+pub use self::render::{Orientation, RenderOptions, TargetSize};
+use self::serialize::{
+    serialize_number, serialize_rect, serialize_stretch_x_area, serialize_stretch_y_area,
+};
+use self::unique::unique_sprites;
+pub use self::unique::NearDuplicate;
 pub use crate::error::{SpreetError, SpreetResult};
 
+mod css;
+mod format;
+mod index;
+mod quantize;
+mod render;
 mod serialize;
+mod unique;
 
 /// A single icon within a spritesheet.
 ///
@@ -27,22 +42,91 @@ pub struct Sprite {
     /// Parsed source SVG image.
     tree: Tree,
     /// Ratio determining the size the destination pixels compared to the source pixels. A ratio of
-    /// 2 means the bitmap will be scaled to be twice the size of the SVG image.
-    pixel_ratio: u8,
+    /// 2 means the bitmap will be scaled to be twice the size of the SVG image. Fractional ratios
+    /// (like 1.5) are allowed.
+    pixel_ratio: f32,
+    /// Transform used to map the SVG image's coordinates onto the bitmap's pixels.
+    transform: Transform,
     /// Bitmap image generated from the SVG image.
     pixmap: Pixmap,
 }
 
+/// How the axes of a sprite's bitmap relate to the axes of its SVG image.
+enum Axes {
+    /// The bitmap's x-axis is the SVG's x-axis (possibly mirrored).
+    Aligned,
+    /// The bitmap's x-axis is the SVG's y-axis, after a quarter turn.
+    Swapped,
+    /// The image was rotated by an angle that isn't a multiple of 90°.
+    Skewed,
+}
+
+/// An SVG image drawn on top of a sprite's base image. See [`Sprite::new_composite`].
+#[derive(Clone)]
+pub struct Overlay {
+    /// Parsed SVG image.
+    pub tree: Tree,
+    /// Horizontal position of the overlay's top-left corner, in the base image's pixels.
+    pub x: f32,
+    /// Vertical position of the overlay's top-left corner, in the base image's pixels.
+    pub y: f32,
+    /// Factor by which the overlay is scaled.
+    pub scale: f32,
+}
+
+impl Overlay {
+    /// Create an overlay that's drawn at its original size at the top-left corner of the base
+    /// image.
+    pub fn new(tree: Tree) -> Self {
+        Self {
+            tree,
+            x: 0.0,
+            y: 0.0,
+            scale: 1.0,
+        }
+    }
+}
+
 impl Sprite {
-    pub fn new(tree: Tree, pixel_ratio: u8) -> Option<Self> {
-        let pixel_ratio_f32 = pixel_ratio.into();
-        let pixmap_size = tree.size().to_int_size().scale_by(pixel_ratio_f32)?;
-        let mut pixmap = Pixmap::new(pixmap_size.width(), pixmap_size.height())?;
-        let render_ts = Transform::from_scale(pixel_ratio_f32, pixel_ratio_f32);
-        resvg::render(&tree, render_ts, &mut pixmap.as_mut());
-        Some(Self {
+    pub fn new(tree: Tree, pixel_ratio: f32) -> SpreetResult<Self> {
+        Self::new_with_options(tree, pixel_ratio, &RenderOptions::default())
+    }
+
+    /// Create a sprite by rasterising an SVG at the size given in the render `options`.
+    ///
+    /// See [`RenderOptions`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SpreetError::EmptyImage`] if the bitmap would have a width or height of zero, or
+    /// [`SpreetError::TooLarge`] if it's too large to allocate.
+    pub fn new_with_options(
+        tree: Tree,
+        pixel_ratio: f32,
+        options: &RenderOptions,
+    ) -> SpreetResult<Self> {
+        let (pixmap, transform) = render_pixmap(&tree, pixel_ratio, options)?;
+        Ok(Self {
+            tree,
+            pixel_ratio,
+            transform,
+            pixmap,
+        })
+    }
+
+    /// Create a sprite from an SVG and a `pixmap` previously rendered from it with the same pixel
+    /// ratio and render `options` (e.g. one loaded from a [`SpriteCache`](crate::SpriteCache)).
+    pub(crate) fn from_pixmap(
+        tree: Tree,
+        pixel_ratio: f32,
+        options: &RenderOptions,
+        pixmap: Pixmap,
+    ) -> SpreetResult<Self> {
+        let (_, transform) = options.layout(&tree, pixel_ratio)?;
+        Ok(Self {
             tree,
             pixel_ratio,
+            transform,
             pixmap,
         })
     }
@@ -66,10 +150,10 @@ impl Sprite {
     ///
     /// JavaScript code for [handling the cut-off][5] is available in Elastic's fork of Fontnik.
     ///
-    /// Note SDF icons are buffered on each side by `3 * pixel_ratio` pixels. An icon with a ratio
-    /// of 1 is buffered by 3px per side, an icon with a ratio of 2 is buffered by 6px per side, and
-    /// so on. This makes SDF sprites wider and higher than the original SVG image by
-    /// `6 * pixel_ratio` pixels in total.
+    /// Note SDF icons are buffered on each side by `3 * pixel_ratio` pixels (rounded to the nearest
+    /// pixel). An icon with a ratio of 1 is buffered by 3px per side, an icon with a ratio of 2 is
+    /// buffered by 6px per side, and so on. This makes SDF sprites wider and higher than the
+    /// original SVG image by `6 * pixel_ratio` pixels in total.
     ///
     /// # Panics
     ///
@@ -81,67 +165,81 @@ impl Sprite {
     /// [3]: https://blog.demofox.org/2014/06/30/distance-field-textures/
     /// [4]: https://docs.mapbox.com/help/troubleshooting/using-recolorable-images-in-mapbox-maps/
     /// [5]: https://github.com/elastic/fontnik/blob/fcaecc174d7561d9147499ba4f254dc7e1b0feea/lib/sdf.js#L225-L230
-    pub fn new_sdf(tree: Tree, pixel_ratio: u8) -> Option<Self> {
-        let pixel_ratio_f32 = pixel_ratio.into();
-        let unbuff_pixmap_size = tree.size().to_int_size().scale_by(pixel_ratio_f32)?;
-        let mut unbuff_pixmap =
-            Pixmap::new(unbuff_pixmap_size.width(), unbuff_pixmap_size.height())?;
-        let render_ts = Transform::from_scale(pixel_ratio_f32, pixel_ratio_f32);
-        resvg::render(&tree, render_ts, &mut unbuff_pixmap.as_mut());
-
-        // Scale the buffer by the pixel ratio so the SDF boundary scales with retina sprites. The
-        // Buffer was originally a fixed size of three pixels, as found in
-        // https://github.com/elastic/spritezero/blob/3b89dc0fef2acbf9/index.js#L144. But after
-        // https://github.com/flother/spreet/issues/86 it was deemed that it should be tied to the
-        // pixel ratio.
-        let buffer = 3 * (pixel_ratio as i32);
-        let mut buff_pixmap = Pixmap::new(
-            unbuff_pixmap_size.width() + 2 * buffer as u32,
-            unbuff_pixmap_size.height() + 2 * buffer as u32,
-        )?;
-        buff_pixmap.draw_pixmap(
-            buffer,
-            buffer,
-            unbuff_pixmap.as_ref(),
-            &PixmapPaint::default(),
-            Transform::default(),
-            None,
-        );
-        let alpha = buff_pixmap
-            .pixels()
-            .iter()
-            .map(|pixel| pixel.alpha())
-            .collect::<Vec<u8>>();
-        let bitmap = BitmapGlyph::new(
-            alpha,
-            unbuff_pixmap_size.width() as usize,
-            unbuff_pixmap_size.height() as usize,
-            buffer as usize,
-        )
-        .ok()?;
-        // Radius and cutoff are recommended to be 8 and 0.25 respectively for a 1x ratio sprite.
-        // https://github.com/stadiamaps/sdf_font_tools/blob/97c5634b8e3515ac7761d0a4f67d12e7f688b042/pbf_font_tools/src/ft_generate.rs#L32-L34
-        // But the radius should scale with the pixel ratio, so that the signed-distance window
-        // remains consistent at higher ratios.
-        let sdf_radius = 8 * (pixel_ratio as usize);
-        let colors = clamp_to_u8(&bitmap.render_sdf(sdf_radius), 0.25)
-            .ok()?
-            .into_iter()
-            .map(|alpha| {
-                Color::from_rgba(0.0, 0.0, 0.0, alpha as f32 / 255.0)
-                    .unwrap()
-                    .premultiply()
-                    .to_color_u8()
-            })
-            .collect::<Vec<_>>();
-        for (i, pixel) in buff_pixmap.pixels_mut().iter_mut().enumerate() {
-            *pixel = colors[i];
-        }
+    pub fn new_sdf(tree: Tree, pixel_ratio: f32) -> SpreetResult<Self> {
+        Self::new_sdf_with_options(tree, pixel_ratio, &RenderOptions::default())
+    }
 
-        Some(Self {
+    /// Create an SDF sprite by rasterising an SVG at the size given in the render `options`.
+    ///
+    /// See [`Sprite::new_sdf`] and [`RenderOptions`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Sprite::new_with_options`], or [`SpreetError::SdfError`] if
+    /// the signed distance field can't be generated.
+    pub fn new_sdf_with_options(
+        tree: Tree,
+        pixel_ratio: f32,
+        options: &RenderOptions,
+    ) -> SpreetResult<Self> {
+        let (pixmap, transform) = render_pixmap(&tree, pixel_ratio, options)?;
+        Ok(Self {
             tree,
             pixel_ratio,
-            pixmap: buff_pixmap,
+            transform,
+            pixmap: sdf_pixmap(&pixmap, pixel_ratio)?,
+        })
+    }
+
+    /// Create a sprite by rasterising a `base` SVG at the size given in the render `options`, and
+    /// then drawing each of the `overlays` on top of it, in order.
+    ///
+    /// The sprite has the base image's size and metadata (e.g. its [content area]), and overlays
+    /// that extend beyond the base image are clipped. Overlays are drawn as vectors, so they stay
+    /// sharp when scaled.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Sprite::new_with_options`].
+    ///
+    /// [content area]: Sprite::content_area
+    pub fn new_composite(
+        base: Tree,
+        overlays: &[Overlay],
+        pixel_ratio: f32,
+        options: &RenderOptions,
+    ) -> SpreetResult<Self> {
+        let (mut pixmap, transform) = render_pixmap(&base, pixel_ratio, options)?;
+        draw_overlays(&mut pixmap, transform, overlays);
+        Ok(Self {
+            tree: base,
+            pixel_ratio,
+            transform,
+            pixmap,
+        })
+    }
+
+    /// Create an SDF sprite from a `base` SVG and `overlays` drawn on top of it. The signed
+    /// distance field is generated from the combined image.
+    ///
+    /// See [`Sprite::new_composite`] and [`Sprite::new_sdf`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Sprite::new_sdf_with_options`].
+    pub fn new_sdf_composite(
+        base: Tree,
+        overlays: &[Overlay],
+        pixel_ratio: f32,
+        options: &RenderOptions,
+    ) -> SpreetResult<Self> {
+        let (mut pixmap, transform) = render_pixmap(&base, pixel_ratio, options)?;
+        draw_overlays(&mut pixmap, transform, overlays);
+        Ok(Self {
+            tree: base,
+            pixel_ratio,
+            transform,
+            pixmap: sdf_pixmap(&pixmap, pixel_ratio)?,
         })
     }
 
@@ -151,7 +249,7 @@ impl Sprite {
     }
 
     /// Get the sprite's pixel ratio.
-    pub fn pixel_ratio(&self) -> u8 {
+    pub fn pixel_ratio(&self) -> f32 {
         self.pixel_ratio
     }
 
@@ -170,10 +268,16 @@ impl Sprite {
     /// Most icons do not specify a content area. But if it is present and the MapLibre/Mapbox map
     /// symbol uses [`icon-text-fit`], the symbol's text will be fitted inside this content box.
     ///
+    /// If the sprite was rendered with an [`Orientation`] that isn't a multiple of 90°, it has no
+    /// content area.
+    ///
     /// [stretchable icon]: https://github.com/mapbox/mapbox-gl-js/issues/8917
     /// [`icon-text-fit`]: https://maplibre.org/maplibre-style-spec/layers/#icon-text-fit
     pub fn content_area(&self) -> Option<Rect> {
-        self.get_node_bbox("mapbox-content")
+        match self.axes() {
+            Axes::Skewed => None,
+            _ => self.get_node_bbox("mapbox-content"),
+        }
     }
 
     /// Metadata for a [stretchable icon].
@@ -186,29 +290,16 @@ impl Sprite {
     ///
     /// Most icons do not specify stretchable areas. See also [`Sprite::content_area`].
     ///
+    /// If the sprite was rendered with an [`Orientation`] that's a quarter turn, the areas come
+    /// from the SVG's vertical stretchable areas, and if the orientation isn't a multiple of 90°
+    /// there are no stretchable areas.
+    ///
     /// [stretchable icon]: https://github.com/mapbox/mapbox-gl-js/issues/8917
     pub fn stretch_x_areas(&self) -> Option<Vec<Rect>> {
-        let mut values = vec![];
-        // First look for an SVG element with the id `mapbox-stretch-x`.
-        if let Some(rect) = self.get_node_bbox("mapbox-stretch-x") {
-            values.push(rect);
-        }
-        // Next look for SVG elements with ids like `mapbox-stretch-x-1`. As soon as one is missing,
-        // stop looking.
-        for i in 1.. {
-            if let Some(rect) = self.get_node_bbox(format!("mapbox-stretch-x-{i}").as_str()) {
-                values.push(rect);
-            } else {
-                break;
-            }
-        }
-        if values.is_empty() {
-            // If there are no SVG elements with `mapbox-stretch-x` ids, check for an element with
-            // the id `mapbox-stretch`. That's a shorthand for stretch-x and stretch-y. If that
-            // exists, use its horizontal coordinates.
-            self.get_node_bbox("mapbox-stretch").map(|rect| vec![rect])
-        } else {
-            Some(values)
+        match self.axes() {
+            Axes::Aligned => self.stretch_areas("x"),
+            Axes::Swapped => self.stretch_areas("y"),
+            Axes::Skewed => None,
         }
     }
 
@@ -222,46 +313,153 @@ impl Sprite {
     ///
     /// Most icons do not specify stretchable areas. See also [`Sprite::content_area`].
     ///
+    /// If the sprite was rendered with an [`Orientation`] that's a quarter turn, the areas come
+    /// from the SVG's horizontal stretchable areas, and if the orientation isn't a multiple of
+    /// 90° there are no stretchable areas.
+    ///
     /// [stretchable icon]: https://github.com/mapbox/mapbox-gl-js/issues/8917
     pub fn stretch_y_areas(&self) -> Option<Vec<Rect>> {
+        match self.axes() {
+            Axes::Aligned => self.stretch_areas("y"),
+            Axes::Swapped => self.stretch_areas("x"),
+            Axes::Skewed => None,
+        }
+    }
+
+    /// Returns the bounding boxes of the SVG's stretchable areas along the given `axis` (`x` or
+    /// `y`).
+    fn stretch_areas(&self, axis: &str) -> Option<Vec<Rect>> {
         let mut values = vec![];
-        // First look for an SVG element with the id `mapbox-stretch-y`.
-        if let Some(rect) = self.get_node_bbox("mapbox-stretch-y") {
+        // First look for an SVG element with the id `mapbox-stretch-x` (or `-y`).
+        if let Some(rect) = self.get_node_bbox(&format!("mapbox-stretch-{axis}")) {
             values.push(rect);
         }
-        // Next look for SVG elements with ids like `mapbox-stretch-y-1`. As soon as one is missing,
+        // Next look for SVG elements with ids like `mapbox-stretch-x-1`. As soon as one is missing,
         // stop looking.
         for i in 1.. {
-            if let Some(rect) = self.get_node_bbox(format!("mapbox-stretch-y-{i}").as_str()) {
+            if let Some(rect) = self.get_node_bbox(&format!("mapbox-stretch-{axis}-{i}")) {
                 values.push(rect);
             } else {
                 break;
             }
         }
         if values.is_empty() {
-            // If there are no SVG elements with `mapbox-stretch-y` ids, check for an element with
+            // If there are no SVG elements with `mapbox-stretch-x` ids, check for an element with
             // the id `mapbox-stretch`. That's a shorthand for stretch-x and stretch-y. If that
-            // exists, use its vertical coordinates.
+            // exists, use its coordinates.
             self.get_node_bbox("mapbox-stretch").map(|rect| vec![rect])
         } else {
             Some(values)
         }
     }
 
+    /// Returns how the axes of the sprite's bitmap relate to the axes of its SVG image.
+    fn axes(&self) -> Axes {
+        let ts = self.transform;
+        if ts.kx == 0.0 && ts.ky == 0.0 {
+            Axes::Aligned
+        } else if ts.sx == 0.0 && ts.sy == 0.0 {
+            Axes::Swapped
+        } else {
+            Axes::Skewed
+        }
+    }
+
     /// Find a node in the SVG tree with a given id, and return its bounding box with coordinates
-    /// multiplied by the sprite's pixel ratio.
+    /// transformed to match the sprite's bitmap (i.e. scaled by the pixel ratio and any target
+    /// size).
     fn get_node_bbox(&self, id: &str) -> Option<Rect> {
-        let bbox = self.tree.node_by_id(id)?.abs_bounding_box();
-        let ratio = self.pixel_ratio as f32;
-        Rect::from_ltrb(
-            bbox.left() * ratio,
-            bbox.top() * ratio,
-            bbox.right() * ratio,
-            bbox.bottom() * ratio,
-        )
+        self.tree
+            .node_by_id(id)?
+            .abs_bounding_box()
+            .transform(self.transform)
+    }
+}
+
+/// Rasterise an SVG tree to a new bitmap, returning the bitmap and the transform used to render it.
+fn render_pixmap(
+    tree: &Tree,
+    pixel_ratio: f32,
+    options: &RenderOptions,
+) -> SpreetResult<(Pixmap, Transform)> {
+    let (pixmap_size, render_ts) = options.layout(tree, pixel_ratio)?;
+    let mut pixmap = new_pixmap(pixmap_size.width(), pixmap_size.height())?;
+    resvg::render(tree, render_ts, &mut pixmap.as_mut());
+    Ok((pixmap, render_ts))
+}
+
+/// Draw each of the `overlays` onto `pixmap`, whose base image was rendered with `transform`.
+fn draw_overlays(pixmap: &mut Pixmap, transform: Transform, overlays: &[Overlay]) {
+    for overlay in overlays {
+        let overlay_ts = transform
+            .pre_translate(overlay.x, overlay.y)
+            .pre_scale(overlay.scale, overlay.scale);
+        resvg::render(&overlay.tree, overlay_ts, &mut pixmap.as_mut());
     }
 }
 
+/// Generate a signed distance field from the alpha channel of `unbuff_pixmap`, returning a new
+/// bitmap buffered on each side by `3 * pixel_ratio` pixels. See [`Sprite::new_sdf`].
+fn sdf_pixmap(unbuff_pixmap: &Pixmap, pixel_ratio: f32) -> SpreetResult<Pixmap> {
+    // Scale the buffer by the pixel ratio so the SDF boundary scales with retina sprites. The
+    // Buffer was originally a fixed size of three pixels, as found in
+    // https://github.com/elastic/spritezero/blob/3b89dc0fef2acbf9/index.js#L144. But after
+    // https://github.com/flother/spreet/issues/86 it was deemed that it should be tied to the
+    // pixel ratio.
+    let buffer = (3.0 * pixel_ratio).round() as i32;
+    let mut buff_pixmap = new_pixmap(
+        unbuff_pixmap.width() + 2 * buffer as u32,
+        unbuff_pixmap.height() + 2 * buffer as u32,
+    )?;
+    buff_pixmap.draw_pixmap(
+        buffer,
+        buffer,
+        unbuff_pixmap.as_ref(),
+        &PixmapPaint::default(),
+        Transform::default(),
+        None,
+    );
+    let alpha = buff_pixmap
+        .pixels()
+        .iter()
+        .map(|pixel| pixel.alpha())
+        .collect::<Vec<u8>>();
+    let bitmap = BitmapGlyph::new(
+        alpha,
+        unbuff_pixmap.width() as usize,
+        unbuff_pixmap.height() as usize,
+        buffer as usize,
+    )?;
+    // Radius and cutoff are recommended to be 8 and 0.25 respectively for a 1x ratio sprite.
+    // https://github.com/stadiamaps/sdf_font_tools/blob/97c5634b8e3515ac7761d0a4f67d12e7f688b042/pbf_font_tools/src/ft_generate.rs#L32-L34
+    // But the radius should scale with the pixel ratio, so that the signed-distance window
+    // remains consistent at higher ratios.
+    let sdf_radius = (8.0 * pixel_ratio).round() as usize;
+    let colors = clamp_to_u8(&bitmap.render_sdf(sdf_radius), 0.25)?
+        .into_iter()
+        .map(|alpha| {
+            Color::from_rgba(0.0, 0.0, 0.0, alpha as f32 / 255.0)
+                .unwrap()
+                .premultiply()
+                .to_color_u8()
+        })
+        .collect::<Vec<_>>();
+    for (i, pixel) in buff_pixmap.pixels_mut().iter_mut().enumerate() {
+        *pixel = colors[i];
+    }
+
+    Ok(buff_pixmap)
+}
+
+/// Create an empty bitmap, returning an error if it has no area or is too large to allocate.
+fn new_pixmap(width: u32, height: u32) -> SpreetResult<Pixmap> {
+    Pixmap::new(width, height).ok_or(if width == 0 || height == 0 {
+        SpreetError::EmptyImage { width, height }
+    } else {
+        SpreetError::TooLarge { width, height }
+    })
+}
+
 /// A description of a sprite image within a spritesheet. Used for the JSON output required by a
 /// Mapbox Style Specification [index file].
 ///
@@ -270,7 +468,8 @@ impl Sprite {
 #[serde(rename_all = "camelCase")]
 pub struct SpriteDescription {
     pub height: u32,
-    pub pixel_ratio: u8,
+    #[serde(serialize_with = "serialize_number")]
+    pub pixel_ratio: f32,
     pub width: u32,
     pub x: u32,
     pub y: u32,
@@ -318,6 +517,10 @@ pub struct SpritesheetBuilder {
     references: Option<MultiMap<String, String>>,
     spacing: u8,
     sdf: bool,
+    quantize: Option<f32>,
+    format: ImageFormat,
+    png_options: PngOptions,
+    near_duplicates: Vec<NearDuplicate>,
 }
 
 impl SpritesheetBuilder {
@@ -327,6 +530,10 @@ impl SpritesheetBuilder {
             references: None,
             spacing: 0,
             sdf: false,
+            quantize: None,
+            format: ImageFormat::Png,
+            png_options: PngOptions::default(),
+            near_duplicates: Vec::new(),
         }
     }
 
@@ -343,29 +550,31 @@ impl SpritesheetBuilder {
 
     // Remove any duplicate sprites from the spritesheet's sprites. This is used to let spritesheets
     // include only unique sprites, with multiple references to the same sprite in the index file.
-    pub fn make_unique(mut self) -> Self {
+    pub fn make_unique(self) -> Self {
+        self.remove_duplicates(None)
+    }
+
+    /// Remove duplicate sprites, as [`SpritesheetBuilder::make_unique`] does, and also treat
+    /// sprites with the same dimensions as duplicates if the root-mean-square difference between
+    /// their bitmaps, on a scale of 0 to 255, is no more than `max_error`.
+    ///
+    /// Sprites merged this way aren't identical, so they're listed by
+    /// [`Spritesheet::near_duplicates`] to let you consolidate the source images.
+    pub fn make_unique_with_tolerance(self, max_error: f32) -> Self {
+        self.remove_duplicates(Some(max_error))
+    }
+
+    fn remove_duplicates(mut self, max_error: Option<f32>) -> Self {
         match self.sprites.take() {
             Some(sprites) => {
-                let mut unique_sprites = BTreeMap::new();
-                let mut references = MultiMap::new();
-                let mut names_for_sprites: BTreeMap<Vec<u8>, String> = BTreeMap::new();
-                for (name, sprite) in sprites {
-                    let sprite_data = sprite.pixmap().encode_png().unwrap();
-                    match names_for_sprites.entry(sprite_data) {
-                        Entry::Occupied(existing_sprite_name) => {
-                            references.insert(existing_sprite_name.get().clone(), name);
-                        }
-                        Entry::Vacant(entry) => {
-                            entry.insert(name.clone());
-                            unique_sprites.insert(name, sprite);
-                        }
-                    }
-                }
-                self.sprites = Some(unique_sprites);
-                self.references = Some(references);
+                let unique = unique_sprites(sprites, max_error);
+                self.sprites = Some(unique.sprites);
+                self.references = Some(unique.references);
+                self.near_duplicates = unique.near_duplicates;
             }
             None => {
                 self.references = None;
+                self.near_duplicates = Vec::new();
             }
         }
         self
@@ -380,13 +589,51 @@ impl SpritesheetBuilder {
         self
     }
 
-    pub fn generate(self) -> Option<Spritesheet> {
-        Spritesheet::new(
+    /// Reduce the spritesheet to an indexed palette of up to 256 colours when it's encoded as a
+    /// PNG.
+    ///
+    /// This is lossy. The quantised spritesheet is only used if the root-mean-square difference
+    /// between it and the original, on a scale of 0 to 255, is no more than `max_error`. See
+    /// [`Spritesheet::encode_png_with_report`]. Only PNG spritesheets can be quantised, so
+    /// [`Self::generate`] returns an error if another [format](Self::format) is used.
+    pub fn quantize(mut self, max_error: f32) -> Self {
+        self.quantize = Some(max_error);
+        self
+    }
+
+    /// Set the image format used to encode and save the spritesheet. Defaults to PNG.
+    pub fn format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the options used to optimise the spritesheet when it's encoded as a PNG.
+    pub fn png_options(mut self, png_options: PngOptions) -> Self {
+        self.png_options = png_options;
+        self
+    }
+
+    /// Pack the sprites into a spritesheet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SpreetError::QuantizeUnsupported`] if the spritesheet is
+    /// [quantised](Self::quantize) but its format isn't PNG. Otherwise, see [`Spritesheet::new`].
+    pub fn generate(self) -> SpreetResult<Spritesheet> {
+        if self.quantize.is_some() && self.format != ImageFormat::Png {
+            return Err(SpreetError::QuantizeUnsupported);
+        }
+        let mut spritesheet = Spritesheet::new(
             self.sprites.unwrap_or_default(),
             self.references.unwrap_or_default(),
             self.spacing,
             self.sdf,
-        )
+        )?;
+        spritesheet.quantize = self.quantize;
+        spritesheet.format = self.format;
+        spritesheet.png_options = self.png_options;
+        spritesheet.near_duplicates = self.near_duplicates;
+        Ok(spritesheet)
     }
 }
 
@@ -394,6 +641,18 @@ impl SpritesheetBuilder {
 pub struct Spritesheet {
     sheet: Pixmap,
     index: BTreeMap<String, SpriteDescription>,
+    /// Maximum error allowed when quantising the spritesheet, or `None` to not quantise it.
+    quantize: Option<f32>,
+    /// Image format used to save the spritesheet.
+    format: ImageFormat,
+    /// Options used to optimise the spritesheet when it's encoded as a PNG.
+    png_options: PngOptions,
+    /// Sprites that were merged with a nearly identical sprite.
+    near_duplicates: Vec<NearDuplicate>,
+    /// Names of sprites that share one image.
+    duplicate_groups: Vec<Vec<String>>,
+    /// Total area, in pixels, of the sprites' bitmaps.
+    sprite_area: usize,
 }
 
 struct PixmapItem {
@@ -402,12 +661,27 @@ struct PixmapItem {
 }
 
 impl Spritesheet {
+    /// Pack `sprites` into a new spritesheet. `references` maps the name of a sprite to other names
+    /// that share its image, and `spacing` is added between sprites.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SpreetError::PackingFailed`], with the name of the largest sprite, if the sprites
+    /// can't be packed within an area ten times their total size, [`SpreetError::EmptyImage`] if
+    /// there are no sprites, or [`SpreetError::TooLarge`] if the spritesheet is too large to
+    /// allocate.
     pub fn new(
         sprites: BTreeMap<String, Sprite>,
         references: MultiMap<String, String>,
         spacing: u8,
         sdf: bool,
-    ) -> Option<Self> {
+    ) -> SpreetResult<Self> {
+        if sprites.is_empty() {
+            return Err(SpreetError::EmptyImage {
+                width: 0,
+                height: 0,
+            });
+        }
         let mut data_items = Vec::new();
         let mut min_area: usize = 0;
 
@@ -431,7 +705,18 @@ impl Spritesheet {
             })
             .collect::<Vec<_>>();
 
-        let PackedItems { items, .. } = crunch::pack_into_po2(min_area * 10, items).ok()?;
+        let PackedItems { items, .. } =
+            crunch::pack_into_po2(min_area * 10, items).map_err(|_| {
+                let largest = data_items
+                    .iter()
+                    .max_by_key(|data| data.sprite.pixmap.width().max(data.sprite.pixmap.height()))
+                    .map(|data| data.name.clone())
+                    .unwrap_or_default();
+                SpreetError::PackingFailed {
+                    required_area: min_area,
+                    largest,
+                }
+            })?;
 
         // There might be some unused space in the packed items --- not all the pixels on
         // the right/bottom edges may have been used. Count the pixels in use so we can
@@ -440,11 +725,13 @@ impl Spritesheet {
         let bin_width = items
             .iter()
             .map(|PackedItem { rect, .. }| rect.right())
-            .max()? as u32;
+            .max()
+            .unwrap_or_default() as u32;
         let bin_height = items
             .iter()
             .map(|PackedItem { rect, .. }| rect.bottom())
-            .max()? as u32;
+            .max()
+            .unwrap_or_default() as u32;
 
         // Final width and height of the spreadsheet will be trimmed of any spacing added to the
         // right and bottom edges.
@@ -455,7 +742,8 @@ impl Spritesheet {
         // using the rectangle locations from the previous step, and store those locations
         // in the vector that will be output as the sprite index file.
         let mut index = BTreeMap::new();
-        let mut sheet = Pixmap::new(final_width, final_height)?;
+        let mut duplicate_groups = Vec::new();
+        let mut sheet = new_pixmap(final_width, final_height)?;
         let pixmap_paint = PixmapPaint::default();
         let pixmap_transform = Transform::default();
         for PackedItem { rect, data } in items {
@@ -489,10 +777,24 @@ impl Spritesheet {
                         SpriteDescription::new(&sprite_rect, &data.sprite, sdf),
                     );
                 }
+                let mut group = vec![data.name.to_string()];
+                group.extend(other_sprite_names.iter().cloned());
+                duplicate_groups.push(group);
             }
         }
 
-        Some(Spritesheet { sheet, index })
+        duplicate_groups.sort();
+
+        Ok(Spritesheet {
+            sheet,
+            index,
+            quantize: None,
+            format: ImageFormat::Png,
+            png_options: PngOptions::default(),
+            near_duplicates: Vec::new(),
+            duplicate_groups,
+            sprite_area: min_area,
+        })
     }
 
     pub fn build() -> SpritesheetBuilder {
@@ -502,33 +804,124 @@ impl Spritesheet {
     /// Encode the spritesheet to the in-memory PNG image.
     ///
     /// The `spritesheet` `Pixmap` is converted to an in-memory PNG, optimised using the [`oxipng`]
-    /// library.
+    /// library with the spritesheet's [`PngOptions`].
     ///
     /// The spritesheet will match an index that can be retrieved with [`Self::get_index`].
     ///
     /// [`oxipng`]: https://github.com/shssoichiro/oxipng
     pub fn encode_png(&self) -> SpreetResult<Vec<u8>> {
-        Ok(optimize_from_memory(
-            self.sheet.encode_png()?.as_slice(),
-            &oxipng::Options {
-                filters: indexset! {oxipng::FilterStrategy::Basic(oxipng::RowFilter::None)},
-                ..oxipng::Options::default()
-            },
-        )?)
+        Ok(self.encode_png_with_report()?.0)
+    }
+
+    /// Encode the spritesheet to an in-memory PNG image, and report on how it was encoded.
+    ///
+    /// If the spritesheet was built with [`SpritesheetBuilder::quantize`], it's encoded both as a
+    /// full-colour PNG and as an indexed PNG. The indexed PNG is returned if its error is within
+    /// the threshold and it's smaller, and the report's [`QuantizationReport`] describes the
+    /// outcome. Otherwise this is the same as [`Self::encode_png`].
+    pub fn encode_png_with_report(&self) -> SpreetResult<(Vec<u8>, PngReport)> {
+        let unoptimized_png = self.sheet.encode_png()?;
+        let unoptimized_size = unoptimized_png.len();
+        let rgba_png = optimize_png(unoptimized_png, &self.png_options)?;
+        let Some(max_error) = self.quantize else {
+            let report = PngReport {
+                unoptimized_size,
+                size: rgba_png.len(),
+                quantization: None,
+            };
+            return Ok((rgba_png, report));
+        };
+        let indexed = IndexedImage::quantize(&self.sheet);
+        let indexed_png = if indexed.error <= max_error {
+            Some(optimize_png(indexed.encode_png()?, &self.png_options)?)
+        } else {
+            None
+        };
+        let quantization = QuantizationReport {
+            colors: indexed.colors(),
+            error: indexed.error,
+            rgba_size: rgba_png.len(),
+            indexed_size: indexed_png.as_ref().map(Vec::len),
+        };
+        let png = match indexed_png {
+            Some(png) if quantization.applied() => png,
+            _ => rgba_png,
+        };
+        let report = PngReport {
+            unoptimized_size,
+            size: png.len(),
+            quantization: Some(quantization),
+        };
+        Ok((png, report))
+    }
+
+    /// Encode the spritesheet to an in-memory image in the spritesheet's [format](Self::format).
+    pub fn encode(&self) -> SpreetResult<Vec<u8>> {
+        match self.format {
+            ImageFormat::Png => self.encode_png(),
+            #[cfg(feature = "webp")]
+            ImageFormat::LosslessWebp => format::encode_webp(&self.sheet, None),
+            #[cfg(feature = "webp")]
+            ImageFormat::Webp { quality } => format::encode_webp(&self.sheet, Some(quality)),
+            #[cfg(feature = "avif")]
+            ImageFormat::Avif { quality } => format::encode_avif(&self.sheet, quality),
+        }
+    }
+
+    /// The image format used to encode and save the spritesheet.
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// Width of the spritesheet, in pixels.
+    pub fn width(&self) -> u32 {
+        self.sheet.width()
+    }
+
+    /// Height of the spritesheet, in pixels.
+    pub fn height(&self) -> u32 {
+        self.sheet.height()
+    }
+
+    /// Total area, in pixels, of the unique sprites in the spritesheet. This is the smallest
+    /// possible area of the spritesheet.
+    pub fn sprite_area(&self) -> usize {
+        self.sprite_area
+    }
+
+    /// The proportion of the spritesheet's area covered by sprites, from 0 to 1.
+    pub fn packing_efficiency(&self) -> f32 {
+        let area = self.width() as usize * self.height() as usize;
+        self.sprite_area as f32 / area.max(1) as f32
     }
 
     /// Saves the spritesheet to a local file named `path`.
     ///
-    /// A spritesheet, called an [image file] in the Mapbox Style Specification, is a PNG image
-    /// containing all the individual sprite images. The `spritesheet` `Pixmap` is converted to an
-    /// in-memory PNG, optimised using the [`oxipng`] library, and saved to a local file.
+    /// A spritesheet, called an [image file] in the Mapbox Style Specification, is an image
+    /// containing all the individual sprite images. The `spritesheet` `Pixmap` is encoded in the
+    /// spritesheet's [format](Self::format) and saved to a local file. By default that's a PNG,
+    /// optimised using the [`oxipng`] library. The file extension of `path` is not checked; see
+    /// [`ImageFormat::extension`].
     ///
     /// The spritesheet will match an index file that can be saved with [`Self::save_index`].
     ///
     /// [image file]: https://docs.mapbox.com/mapbox-gl-js/style-spec/sprite/#image-file
     /// [`oxipng`]: https://github.com/shssoichiro/oxipng
     pub fn save_spritesheet<P: AsRef<Path>>(&self, path: P) -> SpreetResult<()> {
-        Ok(std::fs::write(path, self.encode_png()?)?)
+        Ok(std::fs::write(path, self.encode()?)?)
+    }
+
+    /// Returns the groups of sprite names that share a single image in the spritesheet, because
+    /// duplicates were removed by [`SpritesheetBuilder::make_unique`]. The first name in each group
+    /// is the sprite whose image was kept. Sprites with an image of their own aren't included.
+    pub fn duplicate_groups(&self) -> &[Vec<String>] {
+        &self.duplicate_groups
+    }
+
+    /// Returns the sprites that were merged with a different but nearly identical sprite by
+    /// [`SpritesheetBuilder::make_unique_with_tolerance`].
+    pub fn near_duplicates(&self) -> &[NearDuplicate] {
+        &self.near_duplicates
     }
 
     /// Get the `sprite_index` that can be serialized to JSON.
@@ -554,13 +947,50 @@ impl Spritesheet {
     ///
     /// [index file]: https://docs.mapbox.com/mapbox-gl-js/style-spec/sprite/#index-file
     pub fn save_index(&self, file_name_prefix: &str, minify: bool) -> std::io::Result<()> {
-        let mut file = File::create(format!("{file_name_prefix}.json"))?;
-        let json_string = if minify {
-            serde_json::to_string(&self.get_index())?
-        } else {
-            serde_json::to_string_pretty(&self.get_index())?
+        self.save_index_with_format(file_name_prefix, IndexFormat::MapLibre, minify)
+    }
+
+    /// Encode the `sprite_index` in `format`, for renderers other than MapLibre and Mapbox.
+    ///
+    /// `image_path` is the path of the spritesheet's image relative to the index file, which is
+    /// included in the formats that refer to the image (TexturePacker and XML).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index can't be serialised.
+    pub fn encode_index(
+        &self,
+        format: IndexFormat,
+        image_path: &str,
+        minify: bool,
+    ) -> serde_json::Result<String> {
+        let image = IndexImage {
+            path: image_path,
+            width: self.width(),
+            height: self.height(),
         };
-        write!(file, "{json_string}")?;
+        encode_index(&self.index, format, &image, minify)
+    }
+
+    /// Saves the `sprite_index` in `format` to a local file named `file_name_prefix` + "." +
+    /// [`IndexFormat::extension`].
+    ///
+    /// The index refers to the spritesheet as `file_name_prefix` + "." +
+    /// [`ImageFormat::extension`], without its directory, as saved alongside the index by
+    /// [`Self::save_spritesheet`].
+    pub fn save_index_with_format(
+        &self,
+        file_name_prefix: &str,
+        format: IndexFormat,
+        minify: bool,
+    ) -> std::io::Result<()> {
+        let image_path = Path::new(&format!("{file_name_prefix}.{}", self.format.extension()))
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let index = self.encode_index(format, &image_path, minify)?;
+        let mut file = File::create(format!("{file_name_prefix}.{}", format.extension()))?;
+        write!(file, "{index}")?;
         Ok(())
     }
 }
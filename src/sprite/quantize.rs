@@ -0,0 +1,258 @@
+use std::collections::{BTreeMap, HashMap};
+
+use resvg::tiny_skia::{ColorU8, Pixmap, PremultipliedColorU8};
+
+use crate::error::SpreetResult;
+
+/// Details of an attempt to reduce a spritesheet to an indexed palette of colours.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuantizationReport {
+    /// Number of colours in the palette.
+    pub colors: usize,
+    /// Root-mean-square difference between the original and quantised spritesheets, on a scale of
+    /// 0 to 255.
+    pub error: f32,
+    /// Size in bytes of the spritesheet when encoded as a full-colour PNG.
+    pub rgba_size: usize,
+    /// Size in bytes of the spritesheet when encoded as an indexed PNG, or `None` if the error was
+    /// too large for the quantised spritesheet to be used.
+    pub indexed_size: Option<usize>,
+}
+
+impl QuantizationReport {
+    /// Returns `true` if the quantised spritesheet was used, `false` otherwise.
+    ///
+    /// The quantised spritesheet is only used if its error is within the threshold and it's
+    /// smaller than the full-colour spritesheet.
+    pub fn applied(&self) -> bool {
+        self.indexed_size.is_some_and(|size| size < self.rgba_size)
+    }
+
+    /// Number of bytes saved by quantising the spritesheet.
+    pub fn saving(&self) -> usize {
+        match self.indexed_size {
+            Some(size) if self.applied() => self.rgba_size - size,
+            _ => 0,
+        }
+    }
+}
+
+/// A bitmap image whose pixels are indexes into a palette of up to 256 RGBA colours.
+pub(crate) struct IndexedImage {
+    width: u32,
+    height: u32,
+    palette: Vec<ColorU8>,
+    indices: Vec<u8>,
+    /// Root-mean-square difference between the original and quantised images, on a scale of 0 to
+    /// 255.
+    pub error: f32,
+}
+
+/// A premultiplied RGBA colour.
+type Rgba = [u8; 4];
+
+/// Number of times the palette is refined after the initial median cut.
+const REFINEMENT_ITERATIONS: usize = 5;
+
+impl IndexedImage {
+    /// Reduce `pixmap` to an indexed palette of no more than 256 colours.
+    ///
+    /// If the image has 256 colours or fewer the palette is exact. Otherwise, in the same way as
+    /// [libimagequant], the palette is chosen by a median cut of the image's colour histogram and
+    /// then refined with a few rounds of k-means clustering. One palette entry is reserved for
+    /// fully transparent pixels so that the empty space in a spritesheet stays empty.
+    ///
+    /// [libimagequant]: https://pngquant.org/lib/
+    pub(crate) fn quantize(pixmap: &Pixmap) -> Self {
+        let pixels = pixmap
+            .pixels()
+            .iter()
+            .map(|p| [p.red(), p.green(), p.blue(), p.alpha()])
+            .collect::<Vec<Rgba>>();
+        // A sorted map keeps the palette, and so the encoded PNG, deterministic.
+        let mut histogram: BTreeMap<Rgba, u32> = BTreeMap::new();
+        for pixel in pixels.iter().filter(|pixel| pixel[3] > 0) {
+            *histogram.entry(*pixel).or_default() += 1;
+        }
+        let histogram = histogram.into_iter().collect::<Vec<_>>();
+
+        // Index 0 is always fully transparent.
+        let mut palette = vec![[0; 4]];
+        if histogram.len() < 256 {
+            palette.extend(histogram.iter().map(|(color, _)| *color));
+        } else {
+            palette.extend(refine(&histogram, median_cut(histogram.clone(), 255)));
+        }
+        let palette_index = histogram
+            .iter()
+            .map(|(color, _)| (*color, nearest(&palette[1..], color) as u8 + 1))
+            .collect::<HashMap<_, _>>();
+        // PNG palettes aren't premultiplied.
+        let palette = palette
+            .into_iter()
+            .map(|[r, g, b, a]| {
+                PremultipliedColorU8::from_rgba(r, g, b, a)
+                    .unwrap()
+                    .demultiply()
+            })
+            .collect::<Vec<_>>();
+
+        let mut squared_error = 0.0;
+        let mut indices = Vec::with_capacity(pixels.len());
+        for pixel in &pixels {
+            let index = palette_index.get(pixel).copied().unwrap_or(0);
+            let quantized = palette[index as usize].premultiply();
+            let quantized = [
+                quantized.red(),
+                quantized.green(),
+                quantized.blue(),
+                quantized.alpha(),
+            ];
+            squared_error += distance(pixel, &quantized) as f64;
+            indices.push(index);
+        }
+        let error = (squared_error / (pixels.len().max(1) * 4) as f64).sqrt() as f32;
+
+        Self {
+            width: pixmap.width(),
+            height: pixmap.height(),
+            palette,
+            indices,
+            error,
+        }
+    }
+
+    /// Number of colours in the image's palette.
+    pub(crate) fn colors(&self) -> usize {
+        self.palette.len()
+    }
+
+    /// Encode the image as an (unoptimised) indexed-colour PNG.
+    pub(crate) fn encode_png(&self) -> SpreetResult<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, self.width, self.height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(
+            self.palette
+                .iter()
+                .flat_map(|c| [c.red(), c.green(), c.blue()])
+                .collect::<Vec<_>>(),
+        );
+        encoder.set_trns(self.palette.iter().map(|c| c.alpha()).collect::<Vec<_>>());
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.indices)?;
+        writer.finish()?;
+        Ok(data)
+    }
+}
+
+/// Split a weighted colour histogram into (at most) `count` boxes, and return the weighted mean
+/// colour of each box.
+///
+/// The box with the largest range of values in any one channel, weighted by the number of pixels
+/// in the box, is repeatedly split at its median along that channel.
+fn median_cut(histogram: Vec<(Rgba, u32)>, count: usize) -> Vec<Rgba> {
+    let mut boxes = vec![histogram];
+    while boxes.len() < count {
+        let Some((i, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| {
+                let (channel, range) = widest_channel(colors);
+                let weight: u64 = colors.iter().map(|(_, n)| u64::from(*n)).sum();
+                (i, channel, u64::from(range) * weight)
+            })
+            .max_by_key(|(_, _, score)| *score)
+            .map(|(i, channel, _)| (i, channel))
+        else {
+            break;
+        };
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_unstable_by_key(|(color, _)| color[channel]);
+        let total: u64 = colors.iter().map(|(_, n)| u64::from(*n)).sum();
+        let mut running = 0;
+        let median = colors
+            .iter()
+            .position(|(_, n)| {
+                running += u64::from(*n);
+                running * 2 >= total
+            })
+            .unwrap_or(0);
+        let upper = colors.split_off((median + 1).min(colors.len() - 1));
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+    boxes.iter().map(|colors| mean(colors.iter())).collect()
+}
+
+/// Refine a palette using k-means clustering: assign each colour in the histogram to its nearest
+/// palette entry, then move each entry to the weighted mean of its colours.
+fn refine(histogram: &[(Rgba, u32)], mut palette: Vec<Rgba>) -> Vec<Rgba> {
+    for _ in 0..REFINEMENT_ITERATIONS {
+        let mut clusters = vec![Vec::new(); palette.len()];
+        for entry in histogram {
+            clusters[nearest(&palette, &entry.0)].push(entry);
+        }
+        for (color, cluster) in palette.iter_mut().zip(clusters) {
+            if !cluster.is_empty() {
+                *color = mean(cluster.into_iter());
+            }
+        }
+    }
+    palette
+}
+
+/// Returns the channel with the largest range of values, and that range.
+fn widest_channel(colors: &[(Rgba, u32)]) -> (usize, u8) {
+    (0..4)
+        .map(|channel| {
+            let (min, max) = colors
+                .iter()
+                .fold((u8::MAX, u8::MIN), |(min, max), (c, _)| {
+                    (min.min(c[channel]), max.max(c[channel]))
+                });
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+/// Weighted mean of a set of colours. Colour channels are clamped to the alpha channel so the
+/// result is a valid premultiplied colour.
+fn mean<'a>(colors: impl Iterator<Item = &'a (Rgba, u32)>) -> Rgba {
+    let mut sums = [0u64; 4];
+    let mut total = 0u64;
+    for (color, n) in colors {
+        for (sum, value) in sums.iter_mut().zip(color) {
+            *sum += u64::from(*value) * u64::from(*n);
+        }
+        total += u64::from(*n);
+    }
+    let channel = |sum: u64| (sum as f64 / total.max(1) as f64).round() as u8;
+    let alpha = channel(sums[3]).max(1);
+    [
+        channel(sums[0]).min(alpha),
+        channel(sums[1]).min(alpha),
+        channel(sums[2]).min(alpha),
+        alpha,
+    ]
+}
+
+/// Index of the colour in `palette` closest to `color`.
+fn nearest(palette: &[Rgba], color: &Rgba) -> usize {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| distance(entry, color))
+        .map_or(0, |(i, _)| i)
+}
+
+/// Squared Euclidean distance between two colours.
+fn distance(a: &Rgba, b: &Rgba) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| u32::from(x.abs_diff(*y)).pow(2))
+        .sum()
+}
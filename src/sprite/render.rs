@@ -0,0 +1,270 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use resvg::tiny_skia::{IntSize, Transform};
+use resvg::usvg::{Node, Tree};
+
+use crate::error::{SpreetError, SpreetResult};
+
+/// The size at which a sprite is rendered, before it is scaled by the pixel ratio.
+///
+/// By default a sprite is rendered at the intrinsic size of its SVG image. The other variants
+/// scale the image, preserving its aspect ratio, so that a set of icons authored with inconsistent
+/// `viewBox` sizes can be rendered at a uniform size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TargetSize {
+    /// Render the image at the size defined by the SVG.
+    #[default]
+    Intrinsic,
+    /// Scale the image so it is this many pixels wide.
+    Width(u32),
+    /// Scale the image so it is this many pixels high.
+    Height(u32),
+    /// Scale the image so it is as large as possible while fitting inside this bounding box.
+    Box { width: u32, height: u32 },
+}
+
+/// Options that control how an SVG image is rendered to a sprite's bitmap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RenderOptions {
+    /// The size of the rendered image, in pixels at a pixel ratio of 1.
+    pub size: TargetSize,
+    /// Pad the rendered image with transparent pixels so that it is square. The image is centred
+    /// within the square.
+    pub square: bool,
+    /// Round the scale and translation of the SVG's `viewBox` so that whole units in the SVG land
+    /// on whole pixels in the bitmap. This keeps strokes and edges drawn on the SVG's grid sharp,
+    /// at the cost of a small change in the size or position of the image within the bitmap.
+    pub snap_to_pixels: bool,
+    /// Rotate or mirror the image. The bitmap is resized to fit the turned image.
+    pub orientation: Orientation,
+}
+
+/// A rotation and reflection applied to an SVG image when it's rendered, used to make variants of
+/// an icon such as arrows pointing in different directions.
+///
+/// The image is mirrored first, then rotated about its centre. An orientation can be parsed from,
+/// and formatted as, a string like `90`, `mirror` or `mirror-90`, which is also used as the suffix
+/// of a variant's sprite name (e.g. `arrow-mirror-90`).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Orientation {
+    /// Clockwise rotation, in degrees.
+    pub rotation: f32,
+    /// Flip the image horizontally.
+    pub mirror: bool,
+}
+
+impl Orientation {
+    /// Returns `true` if the orientation doesn't change the image.
+    pub fn is_identity(&self) -> bool {
+        !self.mirror && self.rotation.rem_euclid(360.0) == 0.0
+    }
+
+    /// Returns the transform that turns an image of size `width` by `height`, and the size of the
+    /// bitmap needed to hold the turned image.
+    fn transform(&self, width: u32, height: u32) -> (Transform, (u32, u32)) {
+        let rotation = self.rotation.rem_euclid(360.0);
+        let (w, h) = (width as f32, height as f32);
+        // Quarter turns swap the width and height exactly; other angles need a bitmap large
+        // enough for the rotated image's bounding box.
+        let size = if rotation % 180.0 == 0.0 {
+            (width, height)
+        } else if rotation % 90.0 == 0.0 {
+            (height, width)
+        } else {
+            let (sin, cos) = rotation.to_radians().sin_cos();
+            let fit = |length: f32| (length - 1e-3).ceil().max(0.0) as u32;
+            (
+                fit(w * cos.abs() + h * sin.abs()),
+                fit(w * sin.abs() + h * cos.abs()),
+            )
+        };
+        let mut transform = Transform::from_translate(-w / 2.0, -h / 2.0);
+        if self.mirror {
+            transform = transform.post_scale(-1.0, 1.0);
+        }
+        transform = transform
+            .post_concat(quarter_turn_or_rotate(rotation))
+            .post_translate(size.0 as f32 / 2.0, size.1 as f32 / 2.0);
+        (transform, size)
+    }
+}
+
+/// Returns a rotation of `degrees` clockwise, exact for quarter turns.
+fn quarter_turn_or_rotate(degrees: f32) -> Transform {
+    match degrees {
+        0.0 => Transform::identity(),
+        90.0 => Transform::from_row(0.0, 1.0, -1.0, 0.0, 0.0, 0.0),
+        180.0 => Transform::from_scale(-1.0, -1.0),
+        270.0 => Transform::from_row(0.0, -1.0, 1.0, 0.0, 0.0, 0.0),
+        _ => Transform::from_rotate(degrees),
+    }
+}
+
+// Orientations are compared and hashed by their effect, so that e.g. -90 and 270 are the same.
+impl PartialEq for Orientation {
+    fn eq(&self, other: &Self) -> bool {
+        self.mirror == other.mirror
+            && self.rotation.rem_euclid(360.0).to_bits()
+                == other.rotation.rem_euclid(360.0).to_bits()
+    }
+}
+
+impl Eq for Orientation {}
+
+impl Hash for Orientation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.mirror.hash(state);
+        self.rotation.rem_euclid(360.0).to_bits().hash(state);
+    }
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rotation = self.rotation.rem_euclid(360.0);
+        match (self.mirror, rotation == 0.0) {
+            (true, true) => write!(f, "mirror"),
+            (true, false) => write!(f, "mirror-{rotation}"),
+            (false, _) => write!(f, "{rotation}"),
+        }
+    }
+}
+
+impl FromStr for Orientation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (mirror, rotation) = match s.strip_prefix("mirror") {
+            Some("") => (true, "0"),
+            Some(rest) => match rest.strip_prefix('-') {
+                Some(rotation) => (true, rotation),
+                None => return Err(format!("unknown orientation \"{s}\"")),
+            },
+            None => (false, s),
+        };
+        let rotation = f32::from_str(rotation)
+            .ok()
+            .filter(|rotation| rotation.is_finite())
+            .ok_or_else(|| format!("unknown orientation \"{s}\""))?;
+        Ok(Self { rotation, mirror })
+    }
+}
+
+impl RenderOptions {
+    /// Calculate the size of the bitmap needed to render an SVG `tree` at the given pixel ratio,
+    /// and the transform that maps the SVG's coordinates onto the bitmap's pixels.
+    ///
+    /// At the SVG's intrinsic size, the SVG's width and height are rounded to whole pixels before
+    /// they're multiplied by the pixel ratio, and the result is rounded again. This keeps sprites
+    /// at different pixel ratios in proportion to one another.
+    ///
+    /// Returns [`SpreetError::EmptyImage`] if the bitmap would have a width or height of zero.
+    pub(crate) fn layout(
+        &self,
+        tree: &Tree,
+        pixel_ratio: f32,
+    ) -> SpreetResult<(IntSize, Transform)> {
+        let svg_size = tree.size();
+        let scale = match self.size {
+            TargetSize::Intrinsic => 1.0,
+            TargetSize::Width(width) => width as f32 / svg_size.width(),
+            TargetSize::Height(height) => height as f32 / svg_size.height(),
+            TargetSize::Box { width, height } => {
+                (width as f32 / svg_size.width()).min(height as f32 / svg_size.height())
+            }
+        };
+        let ((width, height), mut transform) = if self.size == TargetSize::Intrinsic {
+            let svg_size = svg_size.to_int_size();
+            (
+                (
+                    (svg_size.width() as f32 * pixel_ratio).round() as u32,
+                    (svg_size.height() as f32 * pixel_ratio).round() as u32,
+                ),
+                Transform::from_scale(pixel_ratio, pixel_ratio),
+            )
+        } else {
+            let scale = scale * pixel_ratio;
+            (
+                (
+                    (svg_size.width() * scale).round() as u32,
+                    (svg_size.height() * scale).round() as u32,
+                ),
+                Transform::from_scale(scale, scale),
+            )
+        };
+        let content_size =
+            IntSize::from_wh(width, height).ok_or(SpreetError::EmptyImage { width, height })?;
+        if self.snap_to_pixels {
+            transform = snap_to_pixels(tree, content_size, transform);
+        }
+        let content_size = if self.orientation.is_identity() {
+            content_size
+        } else {
+            let (orientation_ts, (width, height)) = self
+                .orientation
+                .transform(content_size.width(), content_size.height());
+            transform = transform.post_concat(orientation_ts);
+            IntSize::from_wh(width, height).ok_or(SpreetError::EmptyImage { width, height })?
+        };
+        if !self.square {
+            return Ok((content_size, transform));
+        }
+        // Centre the image within the square, keeping it aligned to whole pixels.
+        let side = content_size.width().max(content_size.height());
+        let dx = (side - content_size.width()) / 2;
+        let dy = (side - content_size.height()) / 2;
+        transform = transform.post_translate(dx as f32, dy as f32);
+        let size = IntSize::from_wh(side, side).ok_or(SpreetError::EmptyImage {
+            width: side,
+            height: side,
+        })?;
+        Ok((size, transform))
+    }
+}
+
+/// Adjust a render `transform` so that the SVG's user units are aligned to the pixel grid of a
+/// bitmap of size `size`.
+///
+/// The SVG's `viewBox` transform is combined with the render transform, and its scale is rounded
+/// to a whole number of pixels per unit (unless the image is being shrunk, or rounding up would
+/// overflow the bitmap). The image is centred in the bitmap and its translation rounded to a whole
+/// pixel. The returned transform is adjusted so that, when combined with the `viewBox` transform
+/// by resvg, it produces the snapped result.
+fn snap_to_pixels(tree: &Tree, size: IntSize, transform: Transform) -> Transform {
+    // usvg stores the `viewBox` transform in a group that wraps the whole image.
+    let view_box_ts = match tree.root().children() {
+        [Node::Group(group)] => group.transform(),
+        _ => Transform::default(),
+    };
+    let Some(inverse_view_box_ts) = view_box_ts.invert() else {
+        return transform;
+    };
+    let combined = transform.pre_concat(view_box_ts);
+    if !combined.is_scale_translate() || !view_box_ts.is_scale_translate() {
+        return transform;
+    }
+    // The `viewBox` origin, in SVG user units.
+    let origin_x = -view_box_ts.tx / view_box_ts.sx;
+    let origin_y = -view_box_ts.ty / view_box_ts.sy;
+    let (sx, tx) = snap_axis(combined.sx, size.width() as f32, origin_x);
+    let (sy, ty) = snap_axis(combined.sy, size.height() as f32, origin_y);
+    Transform::from_row(sx, 0.0, 0.0, sy, tx, ty).pre_concat(inverse_view_box_ts)
+}
+
+/// Snap the `scale` (pixels per SVG unit) along one axis of a bitmap `extent` pixels long, and
+/// return the snapped scale and translation. `origin` is the SVG unit that maps to the bitmap's
+/// edge.
+fn snap_axis(scale: f32, extent: f32, origin: f32) -> (f32, f32) {
+    let units = extent / scale;
+    let snapped_scale = if scale < 1.0 {
+        scale
+    } else if scale.round() * units <= extent + f32::EPSILON {
+        scale.round()
+    } else {
+        scale.floor()
+    };
+    let offset = (extent - units * snapped_scale) / 2.0;
+    (snapped_scale, (offset - origin * snapped_scale).round())
+}
@@ -2,6 +2,18 @@ use resvg::usvg::Rect;
 use serde::ser::SerializeSeq;
 use serde::{Serialize, Serializer};
 
+/// Custom Serde field serialiser for an [`f32`].
+///
+/// Serialises an `f32` with a zero fractional part as a [`u32`], and otherwise as an `f32` rounded
+/// to three decimal places. Used to serialise a sprite's pixel ratio, so that integer ratios are
+/// output as `2` rather than `2.0`.
+pub fn serialize_number<S>(num: &f32, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    icon_number(*num).serialize(serializer)
+}
+
 /// Custom Serde field serialiser for [`Rect`].
 ///
 /// Serialises an [`f32`] with a zero fractional part as a [`u32`], and otherwise as an `f32`
@@ -0,0 +1,123 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+use multimap::MultiMap;
+use resvg::tiny_skia::Pixmap;
+
+use super::Sprite;
+
+/// A sprite that was merged with a different, but nearly identical, sprite when duplicates were
+/// removed with [`SpritesheetBuilder::make_unique_with_tolerance`].
+///
+/// [`SpritesheetBuilder::make_unique_with_tolerance`]: crate::SpritesheetBuilder::make_unique_with_tolerance
+#[derive(Clone, Debug, PartialEq)]
+pub struct NearDuplicate {
+    /// Name of the sprite that was removed.
+    pub name: String,
+    /// Name of the sprite whose image is used in its place.
+    pub duplicate_of: String,
+    /// Root-mean-square difference between the two images, on a scale of 0 to 255.
+    pub error: f32,
+}
+
+/// The result of removing duplicate sprites.
+pub(crate) struct UniqueSprites {
+    pub(crate) sprites: BTreeMap<String, Sprite>,
+    /// Names of removed sprites, keyed by the name of the sprite whose image they share.
+    pub(crate) references: MultiMap<String, String>,
+    pub(crate) near_duplicates: Vec<NearDuplicate>,
+}
+
+/// Remove sprites whose bitmaps are identical to, or (if `max_error` is given) within `max_error`
+/// of, an earlier sprite.
+///
+/// Sprites are compared in name order, so the first name (alphabetically) in each set of
+/// duplicates is the one that's kept. Identical bitmaps are found by hashing their dimensions and
+/// pixels, and confirmed by comparing their pixels. Near-identical bitmaps must have the same
+/// dimensions.
+pub(crate) fn unique_sprites(
+    sprites: BTreeMap<String, Sprite>,
+    max_error: Option<f32>,
+) -> UniqueSprites {
+    let mut unique = UniqueSprites {
+        sprites: BTreeMap::new(),
+        references: MultiMap::new(),
+        near_duplicates: Vec::new(),
+    };
+    // Names of unique sprites, keyed by a hash of their bitmaps.
+    let mut names_for_hashes: HashMap<u64, Vec<String>> = HashMap::new();
+    // Names of unique sprites, keyed by their bitmaps' dimensions.
+    let mut names_for_sizes: HashMap<(u32, u32), Vec<String>> = HashMap::new();
+    for (name, sprite) in sprites {
+        let pixmap = sprite.pixmap();
+        let hash = hash_pixmap(pixmap);
+        let identical = names_for_hashes.get(&hash).and_then(|names| {
+            names
+                .iter()
+                .find(|other| unique.sprites[*other].pixmap() == pixmap)
+        });
+        if let Some(other) = identical {
+            unique.references.insert(other.clone(), name);
+            continue;
+        }
+        let size = (pixmap.width(), pixmap.height());
+        if let Some(max_error) = max_error {
+            let nearest = names_for_sizes
+                .get(&size)
+                .into_iter()
+                .flatten()
+                .filter_map(|other| {
+                    let error = difference(unique.sprites[other].pixmap(), pixmap, max_error)?;
+                    Some((other, error))
+                })
+                .min_by(|(_, a), (_, b)| a.total_cmp(b));
+            if let Some((other, error)) = nearest {
+                unique.references.insert(other.clone(), name.clone());
+                unique.near_duplicates.push(NearDuplicate {
+                    name,
+                    duplicate_of: other.clone(),
+                    error,
+                });
+                continue;
+            }
+        }
+        names_for_hashes.entry(hash).or_default().push(name.clone());
+        names_for_sizes.entry(size).or_default().push(name.clone());
+        unique.sprites.insert(name, sprite);
+    }
+    unique
+}
+
+/// Hash a bitmap's dimensions and pixels.
+fn hash_pixmap(pixmap: &Pixmap) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    pixmap.width().hash(&mut hasher);
+    pixmap.height().hash(&mut hasher);
+    pixmap.data().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Root-mean-square difference between two bitmaps of the same size, on a scale of 0 to 255, or
+/// `None` if it's greater than `max_error`.
+fn difference(a: &Pixmap, b: &Pixmap, max_error: f32) -> Option<f32> {
+    let samples = a.data().len().max(1) as f64;
+    let max_squared_error = f64::from(max_error).powi(2) * samples;
+    let mut squared_error = 0.0;
+    // Compare a row at a time, so very different bitmaps are rejected early.
+    for (row_a, row_b) in a
+        .data()
+        .chunks(a.width() as usize * 4)
+        .zip(b.data().chunks(b.width() as usize * 4))
+    {
+        squared_error += row_a
+            .iter()
+            .zip(row_b)
+            .map(|(x, y)| f64::from(x.abs_diff(*y)).powi(2))
+            .sum::<f64>();
+        if squared_error > max_squared_error {
+            return None;
+        }
+    }
+    Some((squared_error / samples).sqrt() as f32)
+}
@@ -0,0 +1,218 @@
+use std::collections::BTreeSet;
+use std::fs::read;
+use std::path::Path;
+
+use ignore::overrides::{Override, OverrideBuilder};
+use serde_json::Value;
+
+use crate::error::SpreetResult;
+
+/// Layout and paint properties whose values are icon names.
+const ICON_PROPERTIES: [&str; 5] = [
+    "icon-image",
+    "fill-pattern",
+    "line-pattern",
+    "fill-extrusion-pattern",
+    "background-pattern",
+];
+
+/// The names of the icons a [MapLibre] or Mapbox style can refer to, used to build a spritesheet
+/// that contains only the sprites the style needs.
+///
+/// Icon names are collected from the `icon-image` and `*-pattern` properties of the style's
+/// layers, and from `image` expressions in `text-field` properties. A property can be a plain
+/// name, an [expression], or a legacy function with stops. Every name an expression can evaluate
+/// to is collected from its literal branches (e.g. each output of a `match` expression), but
+/// names computed from feature data (e.g. `["get", "icon"]` or `"{class}-15"`) can't be known in
+/// advance. Those layers are listed in [`StyleIcons::dynamic_layers`], and their icons can be
+/// included with [`StyleIcons::allow`].
+///
+/// [MapLibre]: https://maplibre.org/maplibre-style-spec/
+/// [expression]: https://maplibre.org/maplibre-style-spec/expressions/
+#[derive(Clone, Debug)]
+pub struct StyleIcons {
+    names: BTreeSet<String>,
+    dynamic_layers: BTreeSet<String>,
+    allowlist: Override,
+}
+
+impl StyleIcons {
+    /// Collect the icon names from a parsed style.
+    pub fn from_style(style: &Value) -> Self {
+        let mut names = BTreeSet::new();
+        let mut dynamic_layers = BTreeSet::new();
+        let layers = style.get("layers").and_then(Value::as_array);
+        for layer in layers.into_iter().flatten() {
+            let id = layer.get("id").and_then(Value::as_str).unwrap_or_default();
+            let properties = ["layout", "paint"]
+                .into_iter()
+                .filter_map(|section| layer.get(section).and_then(Value::as_object))
+                .flatten();
+            for (property, value) in properties {
+                let complete = if ICON_PROPERTIES.contains(&property.as_str()) {
+                    collect_names(value, &mut names)
+                } else if property == "text-field" {
+                    collect_images(value, &mut names)
+                } else {
+                    true
+                };
+                if !complete {
+                    dynamic_layers.insert(id.to_string());
+                }
+            }
+        }
+        Self {
+            names,
+            dynamic_layers,
+            allowlist: Override::empty(),
+        }
+    }
+
+    /// Read a style from a JSON file and collect its icon names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or isn't valid JSON.
+    pub fn load<P: AsRef<Path>>(path: P) -> SpreetResult<Self> {
+        let style: Value = serde_json::from_slice(&read(path)?)?;
+        Ok(Self::from_style(&style))
+    }
+
+    /// Also include the icons whose names match any of the glob `patterns` (e.g. `shield-*`),
+    /// such as icons whose names are computed from feature data.
+    ///
+    /// Patterns use the same syntax as a `.gitignore` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a pattern is invalid.
+    pub fn allow<S: AsRef<str>>(mut self, patterns: &[S]) -> SpreetResult<Self> {
+        let mut builder = OverrideBuilder::new("");
+        for pattern in patterns {
+            builder.add(pattern.as_ref())?;
+        }
+        self.allowlist = builder.build()?;
+        Ok(self)
+    }
+
+    /// The icon names the style refers to, not including names matched by
+    /// [`StyleIcons::allow`].
+    pub fn names(&self) -> &BTreeSet<String> {
+        &self.names
+    }
+
+    /// The ids of the layers whose icon names are computed from feature data, and so can't all be
+    /// collected.
+    pub fn dynamic_layers(&self) -> &BTreeSet<String> {
+        &self.dynamic_layers
+    }
+
+    /// Returns `true` if the style refers to the icon `name`, or it's allowed by
+    /// [`StyleIcons::allow`].
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name) || self.allowlist.matched(name, false).is_whitelist()
+    }
+}
+
+/// Add the icon names that `value`, the value of an icon property, can evaluate to to `names`.
+/// Returns `false` if some of its names are computed from feature data.
+fn collect_names(value: &Value, names: &mut BTreeSet<String>) -> bool {
+    match value {
+        // A legacy token such as `{class}-15`.
+        Value::String(name) if name.contains('{') => false,
+        Value::String(name) => {
+            if !name.is_empty() {
+                names.insert(name.clone());
+            }
+            true
+        }
+        Value::Array(expression) => collect_expression_names(expression, names),
+        // A legacy function, where each stop is a pair of an input and an output.
+        Value::Object(function) => {
+            let stops = function.get("stops").and_then(Value::as_array);
+            let outputs = stops
+                .into_iter()
+                .flatten()
+                .filter_map(|stop| stop.get(1))
+                .chain(function.get("default"));
+            let complete = collect_all(outputs, names);
+            // An identity function, or one without any stops, uses the value of a feature's
+            // property as the icon name.
+            let is_identity = function.get("type").and_then(Value::as_str) == Some("identity");
+            let has_stops = stops.is_some_and(|stops| !stops.is_empty());
+            let uses_property = is_identity || (!has_stops && function.contains_key("property"));
+            complete && !uses_property
+        }
+        _ => true,
+    }
+}
+
+/// Add the icon names that an `expression` can evaluate to to `names`. Returns `false` if some of
+/// its names are computed from feature data.
+fn collect_expression_names(expression: &[Value], names: &mut BTreeSet<String>) -> bool {
+    let Some((Value::String(operator), args)) = expression.split_first() else {
+        return true;
+    };
+    // The arguments that are the expression's possible results.
+    let outputs: Vec<&Value> = match operator.as_str() {
+        "literal" | "image" => args.iter().take(1).collect(),
+        "coalesce" => args.iter().collect(),
+        // `["case", condition, output, ..., fallback]`
+        "case" => args
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 2 == 1 || *i == args.len() - 1)
+            .map(|(_, arg)| arg)
+            .collect(),
+        // `["match", input, label, output, ..., fallback]`
+        "match" => args
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (*i > 0 && i % 2 == 0) || *i == args.len() - 1)
+            .map(|(_, arg)| arg)
+            .collect(),
+        // `["step", input, output, stop, output, ...]`
+        "step" => args.iter().skip(1).step_by(2).collect(),
+        // `["let", name, value, ..., body]`
+        "let" => args.last().into_iter().collect(),
+        "concat" => {
+            let parts = args.iter().map(Value::as_str).collect::<Option<Vec<_>>>();
+            return match parts {
+                Some(parts) => collect_names(&Value::String(parts.concat()), names),
+                None => false,
+            };
+        }
+        // Anything else, such as `get` or `to-string`, depends on feature data.
+        _ => return false,
+    };
+    collect_all(outputs.into_iter(), names)
+}
+
+/// Add the icon names from each of `values` to `names`. Returns `false` if some of their names
+/// are computed from feature data.
+fn collect_all<'a>(values: impl Iterator<Item = &'a Value>, names: &mut BTreeSet<String>) -> bool {
+    // Collect from every value, even after one is found to be incomplete.
+    let mut complete = true;
+    for value in values {
+        complete &= collect_names(value, names);
+    }
+    complete
+}
+
+/// Add the names of the icons in `image` expressions inside `value`, the value of a `text-field`
+/// property, to `names`. Returns `false` if some of their names are computed from feature data.
+fn collect_images(value: &Value, names: &mut BTreeSet<String>) -> bool {
+    match value {
+        Value::Array(expression) if expression.first() == Some(&Value::from("image")) => {
+            collect_expression_names(expression, names)
+        }
+        Value::Array(values) => {
+            let mut complete = true;
+            for value in values {
+                complete &= collect_images(value, names);
+            }
+            complete
+        }
+        _ => true,
+    }
+}
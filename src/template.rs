@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::fs::read;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use resvg::tiny_skia::Rect;
+use resvg::usvg::{roxmltree, Group, Node, Tree};
+use serde::Deserialize;
+
+use crate::error::{SpreetError, SpreetResult};
+use crate::fs::{parse_document, svg_data_to_text, tree_from_document, LoadOptions};
+
+/// An SVG image containing `{placeholders}`, used to generate a set of similar sprites such as
+/// numbered highway shields.
+///
+/// A placeholder is a name made of letters, digits, `_` and `-` in curly brackets. Placeholders
+/// can appear anywhere in the SVG, e.g. in a `<text>` element (`<text>{ref}</text>`) or in an
+/// attribute (`fill="{color}"`), and each is replaced by a value when a sprite is generated.
+///
+/// If the template has a `mapbox-content` element (see [`Sprite::content_area`]), the image grows
+/// wider when its text is wider than the content area. The `width` and `viewBox` of the root
+/// `<svg>` element, and the `width` of any `<rect>` elements that mark the content area and
+/// stretchable areas, grow by the difference. Shapes that should grow with the image should be
+/// sized using percentages (e.g. `width="100%"`, or `x="50%"` for centred text).
+///
+/// [`Sprite::content_area`]: crate::Sprite::content_area
+#[derive(Clone, Debug)]
+pub struct SpriteTemplate {
+    svg: String,
+    path: PathBuf,
+}
+
+/// Values for the placeholders in a [`SpriteTemplate`].
+///
+/// In a [`TemplateSet`] file, a string fills the `{ref}` placeholder and an object gives a value
+/// for each named placeholder.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum TemplateValues {
+    /// A value for the `{ref}` placeholder.
+    Ref(String),
+    /// Values for placeholders, keyed by name.
+    Map(BTreeMap<String, String>),
+}
+
+/// A template and the values used to generate sprites from it.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TemplateSet {
+    /// Path to the template SVG.
+    pub template: PathBuf,
+    /// Pattern used to name each sprite, containing placeholders (e.g. `shield-us-{ref}`).
+    pub name: String,
+    /// Values for placeholders that are the same for every sprite, unless overridden in
+    /// `values`.
+    #[serde(default)]
+    pub defaults: BTreeMap<String, String>,
+    /// One set of values per sprite.
+    pub values: Vec<TemplateValues>,
+}
+
+impl TemplateSet {
+    /// Returns the values for each sprite, keyed by placeholder name and including the defaults.
+    pub fn value_maps(&self) -> Vec<BTreeMap<String, String>> {
+        self.values
+            .iter()
+            .map(|values| {
+                let mut map = self.defaults.clone();
+                map.extend(values.to_map());
+                map
+            })
+            .collect()
+    }
+}
+
+impl TemplateValues {
+    /// Returns the values keyed by placeholder name.
+    pub fn to_map(&self) -> BTreeMap<String, String> {
+        match self {
+            Self::Ref(value) => BTreeMap::from([(String::from("ref"), value.clone())]),
+            Self::Map(values) => values.clone(),
+        }
+    }
+}
+
+impl SpriteTemplate {
+    /// Create a template from SVG source. `path` is used to resolve relative URLs in the SVG.
+    pub fn new<S: Into<String>, P: Into<PathBuf>>(svg: S, path: P) -> Self {
+        Self {
+            svg: svg.into(),
+            path: path.into(),
+        }
+    }
+
+    /// Load a template from an SVG (or SVGZ) file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or isn't UTF-8 text.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> SpreetResult<Self> {
+        let path = path.as_ref();
+        let data = read(path)?;
+        let svg = svg_data_to_text(&data)?.into_owned();
+        Ok(Self::new(svg, path))
+    }
+
+    /// Path to the template's SVG file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Generate SVG source by replacing the template's placeholders with `values`, growing the
+    /// image if the text doesn't fit its content area.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a placeholder has no value, or the result isn't a valid SVG image.
+    pub fn render_svg(
+        &self,
+        values: &BTreeMap<String, String>,
+        options: &LoadOptions,
+    ) -> SpreetResult<String> {
+        let svg = fill_placeholders(&self.svg, values, true)?;
+        let doc = parse_document(&svg)?;
+        let tree = tree_from_document(&doc, &self.path, options)?;
+        let edits = match (text_bbox(tree.root()), content_bbox(&tree)) {
+            (Some(text), Some(content)) if text.width() > content.width() => {
+                grow(&doc, (text.width() - content.width()).ceil())
+            }
+            _ => Vec::new(),
+        };
+        Ok(apply_edits(&svg, edits))
+    }
+
+    /// Generate an SVG image by replacing the template's placeholders with `values`. See
+    /// [`SpriteTemplate::render_svg`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a placeholder has no value, or the result isn't a valid SVG image.
+    pub fn render_tree(
+        &self,
+        values: &BTreeMap<String, String>,
+        options: &LoadOptions,
+    ) -> SpreetResult<Tree> {
+        let svg = self.render_svg(values, options)?;
+        tree_from_document(&parse_document(&svg)?, &self.path, options)
+    }
+}
+
+/// Read a list of [`TemplateSet`]s from a JSON file. Template paths are relative to the file.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or isn't a valid list of template sets.
+pub fn load_template_sets<P: AsRef<Path>>(path: P) -> SpreetResult<Vec<TemplateSet>> {
+    let path = path.as_ref();
+    let mut sets: Vec<TemplateSet> = serde_json::from_slice(&read(path)?)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for set in &mut sets {
+        set.template = dir.join(&set.template);
+    }
+    Ok(sets)
+}
+
+/// Replace each placeholder in `text` with its value, escaping the values for XML if `escape` is
+/// `true`.
+pub(crate) fn fill_placeholders(
+    text: &str,
+    values: &BTreeMap<String, String>,
+    escape: bool,
+) -> SpreetResult<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let name = rest[1..]
+            .find('}')
+            .map(|end| &rest[1..=end])
+            .filter(|name| {
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            });
+        let Some(name) = name else {
+            // Not a placeholder (e.g. a CSS rule), so keep the bracket.
+            result.push('{');
+            rest = &rest[1..];
+            continue;
+        };
+        let value = values
+            .get(name)
+            .ok_or_else(|| SpreetError::MissingTemplateValue(name.to_string()))?;
+        if escape {
+            result.push_str(&escape_xml(value));
+        } else {
+            result.push_str(value);
+        }
+        rest = &rest[name.len() + 2..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Escape the characters in `s` that have special meaning in XML.
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns the bounding box of all text in `group`, or `None` if there is no text.
+fn text_bbox(group: &Group) -> Option<Rect> {
+    let mut bbox: Option<Rect> = None;
+    for node in group.children() {
+        let rect = match node {
+            Node::Text(_) => Some(node.abs_bounding_box()),
+            Node::Group(group) => text_bbox(group),
+            _ => None,
+        };
+        bbox = match (bbox, rect) {
+            (Some(a), Some(b)) => Some(
+                Rect::from_ltrb(
+                    a.left().min(b.left()),
+                    a.top().min(b.top()),
+                    a.right().max(b.right()),
+                    a.bottom().max(b.bottom()),
+                )
+                .unwrap_or(a),
+            ),
+            (a, b) => a.or(b),
+        };
+    }
+    bbox
+}
+
+/// Returns the bounding box of the template's content area, if it has one.
+fn content_bbox(tree: &Tree) -> Option<Rect> {
+    Some(tree.node_by_id("mapbox-content")?.abs_bounding_box())
+}
+
+/// Returns the edits needed to make the image `extra` pixels wider.
+fn grow(doc: &roxmltree::Document, extra: f32) -> Vec<(Range<usize>, String)> {
+    let mut edits = Vec::new();
+    let root = doc.root_element();
+    let width = root.attribute_node("width");
+    let view_box = root.attribute_node("viewBox");
+    let width_value = width.and_then(|attr| parse_length(attr.value()));
+    let view_box_value = view_box.and_then(|attr| parse_view_box(attr.value()));
+    // A width with units other than pixels (or a percentage) can't be grown.
+    if width.is_some() && width_value.is_none() {
+        return edits;
+    }
+    // The number of pixels per user unit.
+    let scale = match (width_value, view_box_value) {
+        (Some(width), Some([_, _, vb_width, _])) if vb_width > 0.0 => width / vb_width,
+        _ => 1.0,
+    };
+    if let (Some(attr), Some(width)) = (width, width_value) {
+        edits.push((attr.range_value(), format_number(width + extra)));
+    }
+    if let (Some(attr), Some([x, y, vb_width, vb_height])) = (view_box, view_box_value) {
+        let vb_width = format_number(vb_width + extra / scale);
+        edits.push((
+            attr.range_value(),
+            format!("{x} {y} {vb_width} {vb_height}"),
+        ));
+    }
+    // Grow the markers for the content area and stretchable areas with the image.
+    let markers = doc.descendants().filter(|node| {
+        node.has_tag_name("rect")
+            && node
+                .attribute("id")
+                .is_some_and(|id| id == "mapbox-content" || id.starts_with("mapbox-stretch"))
+    });
+    for node in markers {
+        if let Some(attr) = node.attribute_node("width") {
+            if let Some(width) = parse_length(attr.value()) {
+                edits.push((attr.range_value(), format_number(width + extra / scale)));
+            }
+        }
+    }
+    edits
+}
+
+/// Apply `edits` (ranges of `text` and their replacements, which must not overlap) to `text`.
+fn apply_edits(text: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    let mut result = text.to_string();
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    for (range, replacement) in edits {
+        result.replace_range(range, &replacement);
+    }
+    result
+}
+
+/// Parse a length in pixels, with or without a `px` suffix.
+fn parse_length(s: &str) -> Option<f32> {
+    let s = s.trim();
+    s.strip_suffix("px").unwrap_or(s).trim().parse().ok()
+}
+
+/// Parse the four numbers in a `viewBox` attribute.
+fn parse_view_box(s: &str) -> Option<[f32; 4]> {
+    let mut numbers = s
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().ok());
+    let view_box = [
+        numbers.next()??,
+        numbers.next()??,
+        numbers.next()??,
+        numbers.next()??,
+    ];
+    numbers.next().is_none().then_some(view_box)
+}
+
+/// Format a number without unnecessary decimal places.
+fn format_number(n: f32) -> String {
+    format!("{}", (n * 1000.0).round() / 1000.0)
+}
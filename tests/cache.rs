@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use spreet::{get_svg_input_paths, CacheStats, SpriteCache, SpriteLoader};
+
+#[test]
+fn cached_sprites_match_rendered_sprites() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let cache = SpriteCache::new(temp.path()).unwrap();
+    let paths = get_svg_input_paths("tests/fixtures/svgs", true).unwrap();
+
+    let rendered = SpriteLoader::new(2.0)
+        .load_map(&paths, "tests/fixtures/svgs")
+        .unwrap();
+    let first = SpriteLoader::new(2.0)
+        .cache(cache.clone())
+        .load_map(&paths, "tests/fixtures/svgs")
+        .unwrap();
+    assert_eq!(cache.stats().unwrap().entries, 4);
+    let second = SpriteLoader::new(2.0)
+        .cache(cache.clone())
+        .load_map(&paths, "tests/fixtures/svgs")
+        .unwrap();
+    assert_eq!(cache.stats().unwrap().entries, 4);
+
+    for (name, sprite) in &rendered {
+        assert_eq!(first[name].pixmap(), sprite.pixmap());
+        assert_eq!(second[name].pixmap(), sprite.pixmap());
+        assert_eq!(second[name].content_area(), sprite.content_area());
+    }
+}
+
+#[test]
+fn cache_is_keyed_by_pixel_ratio_and_options() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let cache = SpriteCache::new(temp.path()).unwrap();
+    let paths = get_svg_input_paths("tests/fixtures/svgs", false).unwrap();
+
+    for loader in [
+        SpriteLoader::new(1.0),
+        SpriteLoader::new(2.0),
+        SpriteLoader::new(2.0).make_sdf(),
+    ] {
+        loader
+            .cache(cache.clone())
+            .load_map(&paths, "tests/fixtures/svgs")
+            .unwrap();
+    }
+    assert_eq!(cache.stats().unwrap().entries, 9);
+}
+
+#[test]
+fn cache_can_be_pruned() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let cache = SpriteCache::new(temp.path()).unwrap();
+    let paths = get_svg_input_paths("tests/fixtures/svgs", false).unwrap();
+    SpriteLoader::new(1.0)
+        .cache(cache.clone())
+        .load_map(&paths, "tests/fixtures/svgs")
+        .unwrap();
+    let stats = cache.stats().unwrap();
+    assert_eq!(stats.entries, 3);
+
+    let removed = cache.prune(Some(Duration::from_secs(3600))).unwrap();
+    assert_eq!(removed, CacheStats::default());
+    assert_eq!(cache.prune(None).unwrap(), stats);
+    assert_eq!(cache.stats().unwrap(), CacheStats::default());
+}
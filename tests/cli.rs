@@ -1,6 +1,7 @@
 #![cfg(feature = "cli")]
 
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 
 use assert_cmd::cargo::cargo_bin_cmd;
 use predicates::prelude::*;
@@ -41,6 +42,328 @@ fn spreet_can_output_spritesheet() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn spreet_can_output_spritesheet_with_one_job() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("default"))
+        .arg("--jobs")
+        .arg("1")
+        .assert()
+        .success();
+
+    let expected_spritesheet = Path::new("tests/fixtures/output/default@1x.png");
+    let actual_spritesheet = predicate::path::eq_file(temp.join("default.png"));
+    let expected_index = Path::new("tests/fixtures/output/default@1x.json");
+    let actual_index = predicate::path::eq_file(temp.join("default.json"));
+
+    assert!(actual_spritesheet.eval(expected_spritesheet));
+    assert!(actual_index.eval(expected_index));
+
+    Ok(())
+}
+
+#[test]
+fn spreet_can_use_a_sprite_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let cache_dir = temp.join("cache");
+
+    for _ in 0..2 {
+        let mut cmd = cargo_bin_cmd!("spreet");
+        cmd.arg("tests/fixtures/svgs")
+            .arg(temp.join("default"))
+            .arg("--cache-dir")
+            .arg(&cache_dir)
+            .assert()
+            .success();
+
+        let expected_spritesheet = Path::new("tests/fixtures/output/default@1x.png");
+        let actual_spritesheet = predicate::path::eq_file(temp.join("default.png"));
+        assert!(actual_spritesheet.eval(expected_spritesheet));
+    }
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("cache")
+        .arg("info")
+        .arg(&cache_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("3 cached sprites"));
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("cache")
+        .arg("prune")
+        .arg(&cache_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Removed 3 cached sprites"));
+
+    Ok(())
+}
+
+#[test]
+fn spreet_can_render_text_with_font_files() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/text")
+        .arg(temp.join("no_fonts"))
+        .arg("--no-system-fonts")
+        .assert()
+        .success();
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/text")
+        .arg(temp.join("tuffy"))
+        .arg("--no-system-fonts")
+        .arg("--font")
+        .arg("tests/fixtures/fonts/Tuffy.ttf")
+        .arg("--font-family")
+        .arg("Tuffy")
+        .assert()
+        .success();
+
+    let no_fonts = std::fs::read(temp.join("no_fonts.png"))?;
+    let tuffy = std::fs::read(temp.join("tuffy.png"))?;
+    assert_ne!(no_fonts, tuffy);
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("lint")
+        .arg("tests/fixtures/text/minimal.svg")
+        .arg("--no-system-fonts")
+        .assert()
+        .stdout(predicate::str::contains("system fonts").not());
+
+    Ok(())
+}
+
+#[test]
+fn spreet_renders_cached_sprites_again_when_font_changes() -> Result<(), Box<dyn std::error::Error>>
+{
+    let temp = assert_fs::TempDir::new().unwrap();
+    let cache_dir = temp.join("cache");
+    let font = temp.join("Tuffy.ttf");
+    std::fs::copy("tests/fixtures/fonts/Tuffy.ttf", &font)?;
+
+    for _ in 0..2 {
+        let mut cmd = cargo_bin_cmd!("spreet");
+        cmd.arg("tests/fixtures/text")
+            .arg(temp.join("text"))
+            .arg("--cache-dir")
+            .arg(&cache_dir)
+            .arg("--no-system-fonts")
+            .arg("--font")
+            .arg(&font)
+            .assert()
+            .success();
+        // Make the font look edited before the second build.
+        std::fs::File::options()
+            .write(true)
+            .open(&font)?
+            .set_modified(SystemTime::now() + Duration::from_secs(60))?;
+    }
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("cache")
+        .arg("info")
+        .arg(&cache_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("4 cached sprites"));
+
+    Ok(())
+}
+
+#[test]
+fn spreet_rejects_missing_font_file() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/text")
+        .arg(temp.join("missing_font"))
+        .arg("--font")
+        .arg("tests/fixtures/fonts/missing.ttf")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "must be an existing file or directory",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn spreet_can_generate_sprites_from_templates() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("templates"))
+        .arg("--templates")
+        .arg("tests/fixtures/templates/shields.json")
+        .arg("--no-system-fonts")
+        .arg("--font")
+        .arg("tests/fixtures/fonts")
+        .assert()
+        .success();
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("templates.json"))?)?;
+    assert_eq!(index["circle"]["width"], 20);
+    assert_eq!(index["shield-blue-1"]["width"], 20);
+    assert_eq!(index["shield-blue-1000"]["width"], 33);
+    assert_eq!(
+        index["shield-blue-1000"]["content"],
+        serde_json::json!([3, 3, 30, 17])
+    );
+    assert_eq!(
+        index["shield-red-7"]["stretchX"],
+        serde_json::json!([[4, 16]])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn spreet_can_generate_composite_sprites() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("composite"))
+        .arg("--compositions")
+        .arg("tests/fixtures/composite/compositions.json")
+        .assert()
+        .success();
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("composite.json"))?)?;
+    let names = index.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(names.len(), 3 + 8);
+    assert_eq!(index["poi-square-dot-blue"]["height"], 24);
+    assert_eq!(
+        index["poi-round-bar-red"]["content"],
+        serde_json::json!([4, 3, 16, 15])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn spreet_can_generate_rotated_and_mirrored_variants() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/stretchable")
+        .arg(temp.join("variants"))
+        .arg("--variants")
+        .arg("cn-nths-expy-2-affinity=90,mirror")
+        .assert()
+        .success();
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("variants.json"))?)?;
+    let original = &index["cn-nths-expy-2-affinity"];
+    let turned = &index["cn-nths-expy-2-affinity-90"];
+    assert_eq!(turned["width"], original["height"]);
+    assert_eq!(turned["height"], original["width"]);
+    assert_eq!(turned["stretchX"], serde_json::json!([[7, 18]]));
+    assert_eq!(turned["stretchY"], serde_json::json!([[4, 16]]));
+    assert_eq!(
+        index["cn-nths-expy-2-affinity-mirror"]["content"],
+        original["content"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn spreet_rejects_invalid_variants() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/stretchable")
+        .arg(temp.join("variants"))
+        .arg("--variants")
+        .arg("arrows=90,sideways")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("unknown orientation \"sideways\""));
+}
+
+#[test]
+fn spreet_lint_reports_problems() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("lint")
+        .arg("tests/fixtures/lint")
+        .arg("--grid")
+        .arg("8")
+        .assert()
+        .code(65)
+        .stdout(predicate::str::contains(
+            "tests/fixtures/lint/embedded_raster.svg: embeds a raster image",
+        ))
+        .stdout(predicate::str::contains(
+            "tests/fixtures/lint/huge.svg: is too large (1000x30)",
+        ))
+        .stdout(predicate::str::contains(
+            "tests/fixtures/lint/marker_outside.svg: has a \"mapbox-content\" marker outside the image",
+        ))
+        .stdout(predicate::str::contains("clean.svg").not())
+        .stderr("3 of 4 SVGs have problems\n");
+
+    Ok(())
+}
+
+#[test]
+fn spreet_lint_accepts_a_clean_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("lint")
+        .arg("tests/fixtures/lint/clean.svg")
+        .arg("--grid")
+        .arg("8")
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("No problems found in 1 SVGs\n");
+
+    Ok(())
+}
+
+#[test]
+fn spreet_can_output_build_report() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let report_path = temp.join("report.json");
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("unique"))
+        .arg("--unique")
+        .arg("--report")
+        .arg(&report_path)
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with(
+            "Packed 3 sprites (1 duplicates removed) into a",
+        ));
+
+    let report: serde_json::Value = serde_json::from_slice(&std::fs::read(report_path)?)?;
+    assert_eq!(report["sprites"], 3);
+    assert_eq!(report["duplicatesRemoved"], 1);
+    assert_eq!(
+        report["size"],
+        std::fs::metadata(temp.join("unique.png"))?.len()
+    );
+    assert!(report["unoptimizedSize"].as_u64().unwrap() > 0);
+    assert!(report["packingEfficiency"].as_f64().unwrap() <= 1.0);
+    assert!(report["timings"]["render"].as_f64().is_some());
+
+    Ok(())
+}
+
 #[test]
 fn spreet_can_output_unique_spritesheet() -> Result<(), Box<dyn std::error::Error>> {
     let temp = assert_fs::TempDir::new().unwrap();
@@ -63,6 +386,58 @@ fn spreet_can_output_unique_spritesheet() -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
+#[test]
+fn spreet_can_output_duplicate_groups() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let duplicates_file = temp.join("duplicates.json");
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("unique"))
+        .arg("--unique")
+        .arg("--print-duplicates")
+        .arg("--duplicates-file")
+        .arg(&duplicates_file)
+        .assert()
+        .success()
+        .stdout("another_bicycle, bicycle\n");
+
+    let groups: Vec<Vec<String>> = serde_json::from_slice(&std::fs::read(duplicates_file)?)?;
+    assert_eq!(groups, [["another_bicycle", "bicycle"]]);
+
+    Ok(())
+}
+
+#[test]
+fn spreet_reports_near_duplicate_sprites() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let icons = temp.join("icons");
+    std::fs::create_dir(&icons)?;
+    for (name, fill) in [("a", "#000"), ("b", "#020202")] {
+        std::fs::write(
+            icons.join(format!("{name}.svg")),
+            format!(
+                "<svg xmlns='http://www.w3.org/2000/svg' width='16' height='16'>\
+                 <rect width='16' height='16' fill='{fill}'/></svg>"
+            ),
+        )?;
+    }
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg(&icons)
+        .arg(temp.join("near"))
+        .arg("--unique")
+        .arg("--unique-tolerance")
+        .arg("3")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "\"b\" is nearly identical to \"a\" (error 1.73)",
+        ));
+
+    Ok(())
+}
+
 #[test]
 fn spreet_can_output_retina_spritesheet() -> Result<(), Box<dyn std::error::Error>> {
     let temp = assert_fs::TempDir::new().unwrap();
@@ -222,6 +597,272 @@ fn spreet_can_output_sdf_icons() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[cfg(feature = "webp")]
+#[test]
+fn spreet_can_output_webp_spritesheet() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("default"))
+        .arg("--format")
+        .arg("webp")
+        .assert()
+        .success();
+
+    let expected_index = Path::new("tests/fixtures/output/default@1x.json");
+    let actual_index = predicate::path::eq_file(temp.join("default.json"));
+    assert!(actual_index.eval(expected_index));
+    assert!(temp.join("default.webp").is_file());
+    assert!(!temp.join("default.png").exists());
+
+    Ok(())
+}
+
+#[cfg(feature = "webp")]
+#[test]
+fn spreet_rejects_quantize_with_webp() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("default"))
+        .arg("--format")
+        .arg("webp")
+        .arg("--quantize")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--quantize can only be used with PNG spritesheets",
+        ));
+    assert!(!temp.join("default.webp").exists());
+}
+
+#[test]
+fn spreet_rejects_invalid_quality() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("default"))
+        .arg("--quality")
+        .arg("101")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr("error: invalid value '101' for '--quality <QUALITY>': must be a number from 1 to 100\n\nFor more information, try '--help'.\n");
+}
+
+/// Create a directory of icons containing one valid SVG and two invalid ones.
+fn icons_with_invalid_svgs(temp: &assert_fs::TempDir) -> std::path::PathBuf {
+    let icons = temp.join("icons");
+    std::fs::create_dir(&icons).unwrap();
+    std::fs::copy("tests/fixtures/svgs/bicycle.svg", icons.join("bicycle.svg")).unwrap();
+    std::fs::write(icons.join("broken.svg"), "<svg").unwrap();
+    std::fs::write(icons.join("empty.svg"), "").unwrap();
+    icons
+}
+
+#[test]
+fn spreet_reports_every_invalid_svg() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let icons = icons_with_invalid_svgs(&temp);
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg(&icons)
+        .arg(temp.join("invalid"))
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "broken.svg\": not a valid SVG image",
+        ))
+        .stderr(predicate::str::contains(
+            "empty.svg\": not a valid SVG image",
+        ))
+        .stderr(predicate::str::contains(
+            "Error: 2 of 3 SVGs could not be loaded",
+        ));
+    assert!(!temp.join("invalid.png").exists());
+}
+
+#[test]
+fn spreet_can_skip_invalid_svgs() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let icons = icons_with_invalid_svgs(&temp);
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg(&icons)
+        .arg(temp.join("valid"))
+        .arg("--skip-invalid")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning: ").count(2));
+
+    let index: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(temp.join("valid.json"))?)?;
+    assert_eq!(
+        index.as_object().unwrap().keys().collect::<Vec<_>>(),
+        ["bicycle"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn spreet_reports_sprites_that_would_be_empty() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("tiny"))
+        .arg("--ratio")
+        .arg("0.01")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "circle.svg\": could not render a sprite (Image would be empty (0x0 pixels))",
+        ));
+}
+
+#[test]
+fn spreet_can_exclude_svgs() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("exclude"))
+        .arg("--recursive")
+        .arg("--exclude")
+        .arg("recursive")
+        .arg("--exclude")
+        .arg("*bicycle.svg")
+        .assert()
+        .success();
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("exclude.json"))?)?;
+    let names = index.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(names, ["circle"]);
+
+    Ok(())
+}
+
+#[test]
+fn spreet_can_build_spritesheet_from_manifest() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("manifest"))
+        .arg("--manifest")
+        .arg("-")
+        .write_stdin("circle\nrecursive/bear.svg\n")
+        .assert()
+        .success();
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("manifest.json"))?)?;
+    let names = index.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(names, ["circle", "recursive/bear"]);
+
+    Ok(())
+}
+
+#[test]
+fn spreet_reports_icons_missing_from_manifest() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let manifest = temp.join("manifest.json");
+    std::fs::write(&manifest, r#"["circle", "bus", "tram"]"#).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("manifest"))
+        .arg("--manifest")
+        .arg(&manifest)
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "Error: No SVG found for icon \"bus\" in \"tests/fixtures/svgs\"",
+        ))
+        .stderr(predicate::str::contains(
+            "Error: 2 of 3 icons in the manifest could not be found",
+        ));
+}
+
+#[test]
+fn spreet_rejects_invalid_glob() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("exclude"))
+        .arg("--include")
+        .arg("[")
+        .assert()
+        .failure()
+        .code(64)
+        .stderr(predicate::str::starts_with("Error: Invalid pattern:"));
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn spreet_can_build_spritesheet_from_archive() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/archives/icons.zip")
+        .arg(temp.join("archive"))
+        .arg("--recursive")
+        .assert()
+        .success();
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("archive.json"))?)?;
+    let names = index.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(names, ["bicycle", "circle", "flags/iceland_flag"]);
+
+    Ok(())
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn spreet_can_exclude_files_in_archive() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/archives/icons.zip")
+        .arg(temp.join("archive"))
+        .arg("--recursive")
+        .arg("--exclude")
+        .arg("flags")
+        .assert()
+        .success();
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("archive.json"))?)?;
+    let names = index.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(names, ["bicycle", "circle"]);
+
+    Ok(())
+}
+
+#[test]
+fn spreet_rejects_input_file_that_is_not_an_archive() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs/bicycle.svg")
+        .arg(temp.join("archive"))
+        .assert()
+        .failure()
+        .code(66)
+        .stderr(predicate::str::starts_with("Error: "));
+}
+
 #[test]
 fn spreet_rejects_non_existent_input_directory() {
     let mut cmd = cargo_bin_cmd!("spreet");
@@ -230,7 +871,26 @@ fn spreet_rejects_non_existent_input_directory() {
         .assert()
         .failure()
         .code(2)
-        .stderr("error: invalid value 'does_not_exist' for '<INPUT>': must be an existing directory\n\nFor more information, try '--help'.\n");
+        .stderr("error: invalid value 'does_not_exist' for '<INPUT>': must be an existing directory or archive\n\nFor more information, try '--help'.\n");
+}
+
+#[test]
+fn spreet_can_output_fractional_ratio_spritesheet() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("default@1.5x"))
+        .arg("--ratio")
+        .arg("1.5")
+        .assert()
+        .success();
+
+    let index = std::fs::read_to_string(temp.join("default@1.5x.json"))?;
+    assert!(predicate::str::contains("\"pixelRatio\": 1.5,").eval(&index));
+    assert!(predicate::str::contains("\"width\": 23,").eval(&index));
+
+    Ok(())
 }
 
 #[test]
@@ -260,7 +920,22 @@ fn spreet_rejects_negative_ratio() {
         .assert()
         .failure()
         .code(2)
-        .stderr("error: invalid value ' -3' for '--ratio <RATIO>': invalid digit found in string\n\nFor more information, try '--help'.\n");
+        .stderr("error: invalid value ' -3' for '--ratio <RATIO>': invalid float literal\n\nFor more information, try '--help'.\n");
+}
+
+#[test]
+fn spreet_rejects_invalid_size() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("size"))
+        .arg("--size")
+        .arg("depth=24")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr("error: invalid value 'depth=24' for '--size <SIZE>': unknown size option \"depth=24\"\n\nFor more information, try '--help'.\n");
 }
 
 #[test]
@@ -407,3 +1082,228 @@ fn spreet_can_output_sprites_with_text() -> Result<(), Box<dyn std::error::Error
 
     Ok(())
 }
+
+#[test]
+fn spreet_can_build_spritesheet_with_icons_used_by_style() -> Result<(), Box<dyn std::error::Error>>
+{
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("style"))
+        .arg("--recursive")
+        .arg("--style")
+        .arg("tests/fixtures/styles/style.json")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Warning: icon names in layer \"shops\" depend on feature data",
+        ))
+        .stderr(predicate::str::contains(
+            "Warning: the style uses the icon \"train\", which isn't in the spritesheet",
+        ));
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("style.json"))?)?;
+    let names = index.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(names, ["circle", "recursive/bear"]);
+
+    Ok(())
+}
+
+#[test]
+fn spreet_can_allow_icons_not_found_in_style() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("style"))
+        .arg("--style")
+        .arg("tests/fixtures/styles/style.json")
+        .arg("--allow-icon")
+        .arg("*bicycle")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("depend on feature data").not());
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("style.json"))?)?;
+    let names = index.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(names, ["another_bicycle", "bicycle", "circle"]);
+
+    Ok(())
+}
+
+#[test]
+fn spreet_requires_style_for_allow_icon() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("style"))
+        .arg("--allow-icon")
+        .arg("*bicycle")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("--style <FILE>"));
+}
+
+#[test]
+fn spreet_can_output_css_stylesheet() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("sprite@2x"))
+        .arg("--retina")
+        .arg("--css")
+        .arg("--css-prefix")
+        .arg("icon-")
+        .assert()
+        .success();
+
+    let css = std::fs::read_to_string(temp.join("sprite@2x.css"))?;
+    assert!(css.contains(".icon-circle {\n  width: 20px;\n  height: 20px;\n"));
+    assert!(css.contains("background-image: url(\"sprite@2x.png\");"));
+
+    Ok(())
+}
+
+#[test]
+fn spreet_can_output_css_stylesheet_for_several_pixel_ratios(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("sprite"))
+        .arg("--css")
+        .arg("--css-ratios")
+        .arg("2")
+        .assert()
+        .success();
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("sprite@2x.json"))?)?;
+    assert_eq!(index["circle"]["pixelRatio"], 2);
+    assert!(temp.join("sprite@2x.png").exists());
+    let css = std::fs::read_to_string(temp.join("sprite.css"))?;
+    assert!(css.contains(".sprite-circle {\n  width: 20px;\n  height: 20px;\n"));
+    assert!(css.contains(
+        "background-image: image-set(url(\"sprite.png\") 1x, url(\"sprite@2x.png\") 2x);"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn spreet_names_css_ratio_spritesheets_after_output_ratio() -> Result<(), Box<dyn std::error::Error>>
+{
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    let assert = cmd
+        .arg("tests/fixtures/svgs")
+        .arg(temp.join("sprite@2x"))
+        .arg("--retina")
+        .arg("--css")
+        .arg("--css-ratios")
+        .arg("1,3,3.0")
+        .arg("--quantize")
+        .assert()
+        .success();
+
+    // Each spritesheet is quantised, and the duplicate ratio is only rendered once.
+    let stderr = String::from_utf8(assert.get_output().stderr.clone())?;
+    assert_eq!(stderr.matches("sprite.png").count(), 1);
+    assert_eq!(stderr.matches("sprite@2x.png").count(), 1);
+    assert_eq!(stderr.matches("sprite@3x.png").count(), 1);
+    for file in [
+        "sprite.png",
+        "sprite.json",
+        "sprite@3x.png",
+        "sprite@3x.json",
+    ] {
+        assert!(temp.join(file).exists(), "{file} is missing");
+    }
+    let css = std::fs::read_to_string(temp.join("sprite@2x.css"))?;
+    assert!(css.contains("background-image: url(\"sprite.png\");"));
+    assert!(css.contains("url(\"sprite@2x.png\") 2x"));
+    assert!(css.contains("sprite@3x.png"));
+    assert!(!css.contains("@2x@"));
+
+    Ok(())
+}
+
+#[test]
+fn spreet_requires_css_for_css_ratios() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("sprite"))
+        .arg("--css-ratios")
+        .arg("2")
+        .assert()
+        .failure()
+        .code(2);
+}
+
+#[test]
+fn spreet_can_output_index_in_other_formats() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("atlas"))
+        .arg("--index-format")
+        .arg("xml")
+        .assert()
+        .success();
+
+    let xml = std::fs::read_to_string(temp.join("atlas.xml"))?;
+    assert!(xml.contains("<TextureAtlas imagePath=\"atlas.png\""));
+    assert!(xml.contains("<SubTexture name=\"circle\" x=\"0\" y=\"0\""));
+    assert!(!temp.join("atlas.json").exists());
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.arg("tests/fixtures/svgs")
+        .arg(temp.join("deckgl"))
+        .arg("--index-format")
+        .arg("deckgl")
+        .assert()
+        .success();
+
+    let mapping: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.join("deckgl.json"))?)?;
+    assert_eq!(mapping["circle"]["mask"], false);
+
+    Ok(())
+}
+
+#[test]
+fn spreet_reads_input_directory_named_like_subcommand_as_path() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.join("cache");
+    std::fs::create_dir(&input).unwrap();
+    std::fs::copy("tests/fixtures/svgs/circle.svg", input.join("circle.svg")).unwrap();
+
+    // `cache` on its own is the subcommand, so the directory must be written as a path.
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.current_dir(&temp)
+        .arg("cache")
+        .arg("out")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("unrecognized subcommand 'out'"));
+
+    let mut cmd = cargo_bin_cmd!("spreet");
+    cmd.current_dir(&temp)
+        .arg("./cache")
+        .arg("out")
+        .assert()
+        .success();
+    assert!(temp.join("out.png").is_file());
+}
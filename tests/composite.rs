@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use resvg::usvg::{Options, Tree};
+use spreet::{load_compositions, Overlay, RenderOptions, Sprite, SpriteLoader};
+
+fn tree(svg: &str) -> Tree {
+    Tree::from_str(svg, &Options::default()).unwrap()
+}
+
+#[test]
+fn composition_has_every_combination_of_values() {
+    let compositions = load_compositions("tests/fixtures/composite/compositions.json").unwrap();
+    let combinations = compositions[0].combinations();
+    assert_eq!(combinations.len(), 8);
+    assert_eq!(
+        combinations[0],
+        BTreeMap::from([
+            (String::from("color"), String::from("red")),
+            (String::from("glyph"), String::from("bar")),
+            (String::from("pin"), String::from("round")),
+        ])
+    );
+    assert_eq!(combinations[1]["pin"], "square");
+    assert_eq!(combinations[2]["glyph"], "dot");
+    assert_eq!(combinations[4]["color"], "blue");
+}
+
+#[test]
+fn compositions_are_loaded_relative_to_their_file() {
+    let compositions = load_compositions("tests/fixtures/composite/compositions.json").unwrap();
+    let layers = &compositions[0].layers;
+    assert_eq!(
+        Path::new(&layers[0].source),
+        Path::new("tests/fixtures/composite/pins/{pin}.svg")
+    );
+    assert_eq!(layers[0].offset, [0.0, 0.0]);
+    assert_eq!(layers[0].scale, 1.0);
+    assert_eq!(layers[1].offset, [4.0, 3.0]);
+    assert_eq!(layers[1].scale, 0.5);
+}
+
+#[test]
+fn sprite_can_be_composed_from_layers() {
+    let base = tree(
+        "<svg xmlns='http://www.w3.org/2000/svg' width='10' height='10'>\
+         <rect width='10' height='10' fill='#00f'/></svg>",
+    );
+    let overlay = Overlay {
+        x: 4.0,
+        y: 2.0,
+        scale: 0.5,
+        ..Overlay::new(tree(
+            "<svg xmlns='http://www.w3.org/2000/svg' width='4' height='4'>\
+             <rect width='4' height='4' fill='#f00'/></svg>",
+        ))
+    };
+    let sprite = Sprite::new_composite(base, &[overlay], 2.0, &RenderOptions::default()).unwrap();
+    let pixmap = sprite.pixmap();
+    assert_eq!((pixmap.width(), pixmap.height()), (20, 20));
+    let red = |x, y| pixmap.pixel(x, y).unwrap().red() == 255;
+    // The overlay covers (4, 2) to (6, 4) in the base image, or (8, 4) to (12, 8) in pixels.
+    assert!(red(8, 4));
+    assert!(red(11, 7));
+    assert!(!red(7, 4));
+    assert!(!red(12, 4));
+    assert!(!red(8, 8));
+}
+
+#[test]
+fn loader_renders_compositions() {
+    let compositions = load_compositions("tests/fixtures/composite/compositions.json").unwrap();
+    let sprites = SpriteLoader::new(1.0)
+        .load_compositions(&compositions)
+        .into_iter()
+        .map(|result| result.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(sprites.len(), 8);
+    assert_eq!(sprites[0].0, "poi-round-bar-red");
+    assert_eq!(sprites[7].0, "poi-square-dot-blue");
+    let (_, round) = &sprites[0];
+    assert_eq!((round.pixmap().width(), round.pixmap().height()), (20, 24));
+    // The base image's metadata is kept.
+    assert!(round.content_area().is_some());
+    assert!(sprites[1].1.content_area().is_none());
+}
+
+#[test]
+fn loader_returns_error_for_missing_layer() {
+    let mut compositions = load_compositions("tests/fixtures/composite/compositions.json").unwrap();
+    compositions[0]
+        .values
+        .insert(String::from("glyph"), vec![String::from("missing")]);
+    let results = SpriteLoader::new(1.0).load_compositions(&compositions);
+    let error = results[0].as_ref().err().unwrap();
+    assert_eq!(
+        error.path,
+        Path::new("tests/fixtures/composite/glyphs/missing.svg")
+    );
+}
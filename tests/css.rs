@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use spreet::{load_svg, spritesheet_css, CssImage, CssOptions, Sprite, Spritesheet};
+
+fn spritesheet(names: &[&str], pixel_ratio: f32) -> Spritesheet {
+    let sprites = names
+        .iter()
+        .map(|name| {
+            let tree = load_svg(format!("./tests/fixtures/svgs/{name}.svg")).unwrap();
+            (name.to_string(), Sprite::new(tree, pixel_ratio).unwrap())
+        })
+        .collect::<BTreeMap<_, _>>();
+    Spritesheet::build().sprites(sprites).generate().unwrap()
+}
+
+fn image<'a>(spritesheet: &'a Spritesheet, url: &str) -> CssImage<'a> {
+    CssImage {
+        spritesheet,
+        url: url.to_string(),
+    }
+}
+
+#[test]
+fn css_has_class_for_each_sprite() {
+    let spritesheet = spritesheet(&["bicycle", "circle"], 1.0);
+    let css = spritesheet_css(&[image(&spritesheet, "sprite.png")], &CssOptions::default());
+    assert_eq!(
+        css,
+        ".sprite-bicycle {
+  width: 15px;
+  height: 15px;
+  background-image: url(\"sprite.png\");
+  background-repeat: no-repeat;
+  background-position: -20px 0;
+  background-size: 35px 20px;
+}
+.sprite-circle {
+  width: 20px;
+  height: 20px;
+  background-image: url(\"sprite.png\");
+  background-repeat: no-repeat;
+  background-position: 0 0;
+  background-size: 35px 20px;
+}
+"
+    );
+}
+
+#[test]
+fn css_uses_css_pixels_for_high_pixel_ratios() {
+    let spritesheet = spritesheet(&["bicycle", "circle"], 2.0);
+    let css = spritesheet_css(
+        &[image(&spritesheet, "sprite@2x.png")],
+        &CssOptions::default(),
+    );
+    assert!(css.contains("width: 15px;"));
+    assert!(css.contains("background-position: -20px 0;"));
+    assert!(css.contains("background-size: 35px 20px;"));
+}
+
+#[test]
+fn css_uses_image_set_for_spritesheets_with_same_layout() {
+    let spritesheet_2x = spritesheet(&["bicycle", "circle"], 2.0);
+    let spritesheet_1x = spritesheet(&["bicycle", "circle"], 1.0);
+    let css = spritesheet_css(
+        &[
+            image(&spritesheet_2x, "sprite@2x.png"),
+            image(&spritesheet_1x, "sprite.png"),
+        ],
+        &CssOptions::default(),
+    );
+    assert!(css.contains(
+        "background-image: url(\"sprite.png\");
+  background-image: image-set(url(\"sprite.png\") 1x, url(\"sprite@2x.png\") 2x);"
+    ));
+    assert!(!css.contains("@media"));
+}
+
+#[test]
+fn css_uses_media_query_for_spritesheets_with_different_layout() {
+    let names = ["another_bicycle", "bicycle", "circle", "recursive/bear"];
+    let spritesheet_1x = spritesheet(&names, 1.0);
+    let spritesheet_3x = spritesheet(&names, 3.0);
+    let css = spritesheet_css(
+        &[
+            image(&spritesheet_1x, "sprite.png"),
+            image(&spritesheet_3x, "sprite@3x.png"),
+        ],
+        &CssOptions::default(),
+    );
+    assert!(!css.contains("image-set"));
+    assert!(css.contains("@media (min-resolution: 3dppx) {\n  .sprite-another_bicycle {\n"));
+    assert!(css.contains("    background-image: url(\"sprite@3x.png\");\n"));
+}
+
+#[test]
+fn css_class_names_are_escaped() {
+    let tree = load_svg("./tests/fixtures/svgs/circle.svg").unwrap();
+    let sprites = ["1st", "recursive/circle", "big circle"]
+        .into_iter()
+        .map(|name| (name.to_string(), Sprite::new(tree.clone(), 1.0).unwrap()))
+        .collect();
+    let spritesheet = Spritesheet::build().sprites(sprites).generate().unwrap();
+    let options = CssOptions {
+        class_prefix: String::new(),
+    };
+    let css = spritesheet_css(&[image(&spritesheet, "sprite.png")], &options);
+    assert!(css.contains(".\\31 st {"));
+    assert!(css.contains(".recursive\\/circle {"));
+    assert!(css.contains(".big\\ circle {"));
+}
@@ -1,7 +1,12 @@
+use std::fs::File;
 use std::path::Path;
 
+use assert_fs::prelude::*;
 use assert_matches::assert_matches;
-use spreet::{get_svg_input_paths, SpreetError};
+use spreet::{
+    get_svg_input_paths, get_svg_input_paths_with_filter, load_svg, load_svg_from_bytes,
+    load_svg_from_reader, InputFilter, LoadOptions, SpreetError,
+};
 
 #[test]
 fn get_svg_input_paths_returns_non_recursive_results() {
@@ -47,3 +52,132 @@ fn get_svg_input_paths_returns_error_when_path_is_file() {
         Err(SpreetError::IoError(_))
     );
 }
+
+/// Create an input directory containing an empty file at each of `paths`.
+fn input_dir(paths: &[&str]) -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    for path in paths {
+        temp.child(path).touch().unwrap();
+    }
+    temp
+}
+
+/// Returns the sorted paths found in `dir`, relative to it.
+fn relative_paths(dir: &assert_fs::TempDir, filter: &InputFilter) -> Vec<String> {
+    let mut paths = get_svg_input_paths_with_filter(dir.path(), true, filter)
+        .unwrap()
+        .iter()
+        .map(|path| {
+            let path = path.strip_prefix(dir.path()).unwrap();
+            path.to_string_lossy().replace('\\', "/")
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn get_svg_input_paths_with_filter_excludes_directories() {
+    let dir = input_dir(&[
+        "a.svg",
+        "drafts/b.svg",
+        "icons/_deprecated/c.svg",
+        "icons/d.svg",
+    ]);
+    let filter = InputFilter {
+        exclude: vec![String::from("drafts"), String::from("_deprecated")],
+        ..Default::default()
+    };
+    assert_eq!(relative_paths(&dir, &filter), ["a.svg", "icons/d.svg"]);
+}
+
+#[test]
+fn get_svg_input_paths_with_filter_includes_matching_files() {
+    let dir = input_dir(&["a.svg", "icons/b.svg", "icons/c.svg", "icons/sub/d.svg"]);
+    let filter = InputFilter {
+        include: vec![String::from("icons/**")],
+        exclude: vec![String::from("c.svg")],
+        ..Default::default()
+    };
+    assert_eq!(
+        relative_paths(&dir, &filter),
+        ["icons/b.svg", "icons/sub/d.svg"]
+    );
+}
+
+#[test]
+fn get_svg_input_paths_reads_ignore_files_at_any_level() {
+    let dir = input_dir(&[
+        "a.svg",
+        "b.svg",
+        "icons/a.svg",
+        "icons/b.svg",
+        "icons/c.svg",
+    ]);
+    dir.child(".spreetignore").write_str("b.svg\n").unwrap();
+    dir.child("icons/.spreetignore")
+        .write_str("c.svg\n!b.svg\n")
+        .unwrap();
+    assert_eq!(
+        relative_paths(&dir, &InputFilter::default()),
+        ["a.svg", "icons/a.svg", "icons/b.svg"]
+    );
+    let filter = InputFilter {
+        ignore_files: false,
+        ..Default::default()
+    };
+    assert_eq!(relative_paths(&dir, &filter).len(), 5);
+}
+
+#[test]
+fn get_svg_input_paths_with_filter_returns_error_for_invalid_pattern() {
+    let filter = InputFilter {
+        exclude: vec![String::from("icons/[")],
+        ..Default::default()
+    };
+    assert_matches!(
+        get_svg_input_paths_with_filter("tests/fixtures/svgs", true, &filter),
+        Err(SpreetError::PatternError(_))
+    );
+}
+
+#[test]
+fn load_svg_from_bytes_matches_load_svg() {
+    let path = Path::new("tests/fixtures/svgs/bicycle.svg");
+    let data = std::fs::read(path).unwrap();
+    let tree = load_svg_from_bytes(&data, None, &LoadOptions::default()).unwrap();
+    assert_eq!(tree.size(), load_svg(path).unwrap().size());
+}
+
+#[test]
+fn load_svg_from_reader_accepts_svgz() {
+    let file = File::open("tests/fixtures/svgz/circle.svgz").unwrap();
+    let tree = load_svg_from_reader(file, None, &LoadOptions::default()).unwrap();
+    assert_eq!(
+        tree.size(),
+        load_svg("tests/fixtures/svgs/circle.svg").unwrap().size()
+    );
+}
+
+#[test]
+fn load_svg_from_bytes_loads_resources_with_resolver() {
+    let data = std::fs::read("tests/fixtures/pngs/iceland_flag.svg").unwrap();
+    let resolver = |href: &str| std::fs::read(Path::new("tests/fixtures/pngs").join(href)).ok();
+    let tree = load_svg_from_bytes(&data, Some(&resolver), &LoadOptions::default()).unwrap();
+    assert!(tree.root().has_children());
+}
+
+#[test]
+fn load_svg_from_bytes_skips_resources_without_resolver() {
+    let data = std::fs::read("tests/fixtures/pngs/iceland_flag.svg").unwrap();
+    let tree = load_svg_from_bytes(&data, None, &LoadOptions::default()).unwrap();
+    assert!(!tree.root().has_children());
+}
+
+#[test]
+fn load_svg_from_bytes_returns_error_for_invalid_svg() {
+    assert_matches!(
+        load_svg_from_bytes(b"not an svg", None, &LoadOptions::default()),
+        Err(SpreetError::SvgError(_))
+    );
+}
@@ -0,0 +1,92 @@
+use assert_matches::assert_matches;
+use spreet::{lint_svg, LintIssue, LintOptions, SpreetError};
+
+#[test]
+fn lint_accepts_clean_svg() {
+    let options = LintOptions {
+        grid: Some(8),
+        ..Default::default()
+    };
+    let issues = lint_svg("tests/fixtures/lint/clean.svg", &options).unwrap();
+    assert_eq!(issues, vec![]);
+}
+
+#[test]
+fn lint_reports_references_and_missing_view_box() {
+    let issues = lint_svg(
+        "tests/fixtures/pngs/iceland_flag.svg",
+        &LintOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        issues,
+        vec![
+            LintIssue::ExternalReference("iceland_flag.png".to_string()),
+            LintIssue::MissingViewBox,
+        ]
+    );
+    let issues = lint_svg(
+        "tests/fixtures/lint/embedded_raster.svg",
+        &LintOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(issues, vec![LintIssue::EmbeddedRaster]);
+}
+
+#[test]
+fn lint_reports_text() {
+    let issues = lint_svg("tests/fixtures/text/minimal.svg", &LintOptions::default()).unwrap();
+    assert!(issues.contains(&LintIssue::SystemFontText));
+}
+
+#[test]
+fn lint_reports_size_problems() {
+    let options = LintOptions {
+        max_size: 500.0,
+        grid: Some(8),
+        ..Default::default()
+    };
+    let issues = lint_svg("tests/fixtures/lint/huge.svg", &options).unwrap();
+    assert_eq!(
+        issues,
+        vec![
+            LintIssue::TooLarge {
+                width: 1000.0,
+                height: 30.0
+            },
+            LintIssue::OffGrid {
+                width: 1000.0,
+                height: 30.0,
+                grid: 8
+            },
+        ]
+    );
+}
+
+#[test]
+fn lint_reports_markers_outside_image() {
+    let issues = lint_svg(
+        "tests/fixtures/lint/marker_outside.svg",
+        &LintOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        issues,
+        vec![LintIssue::MarkerOutOfBounds("mapbox-content".to_string())]
+    );
+    let issues = lint_svg(
+        "tests/fixtures/stretchable/shield-rotated.svg",
+        &LintOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(issues, vec![]);
+}
+
+#[test]
+fn lint_returns_error_for_invalid_svg() {
+    let result = lint_svg(
+        "tests/fixtures/pngs/iceland_flag.png",
+        &LintOptions::default(),
+    );
+    assert_matches!(result, Err(SpreetError::SvgError(_)));
+}
@@ -0,0 +1,126 @@
+use std::path::Path;
+
+use assert_matches::assert_matches;
+use spreet::{
+    get_svg_input_paths, load_svg, Orientation, RenderOptions, SpreetError, Sprite, SpriteLoader,
+    TargetSize,
+};
+
+#[test]
+fn loader_returns_results_in_input_order() {
+    let mut paths = get_svg_input_paths("tests/fixtures/svgs", true).unwrap();
+    paths.sort();
+    paths.reverse();
+    let results = SpriteLoader::new(2.0)
+        .jobs(2)
+        .load(&paths, "tests/fixtures/svgs");
+    let names = results
+        .into_iter()
+        .map(|result| result.unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["recursive/bear", "circle", "bicycle", "another_bicycle"]
+    );
+}
+
+#[test]
+fn loader_renders_the_same_sprites_as_sprite_new() {
+    let path = Path::new("tests/fixtures/svgs/bicycle.svg");
+    let sprites = SpriteLoader::new(2.0)
+        .load_map(&[path], "tests/fixtures/svgs")
+        .unwrap();
+    let expected = Sprite::new(load_svg(path).unwrap(), 2.0).unwrap();
+    assert_eq!(sprites.keys().collect::<Vec<_>>(), vec!["bicycle"]);
+    assert_eq!(sprites["bicycle"].pixmap(), expected.pixmap());
+}
+
+#[test]
+fn loader_applies_render_options_by_directory() {
+    let paths = get_svg_input_paths("tests/fixtures/svgs", true).unwrap();
+    let height = |h| RenderOptions {
+        size: TargetSize::Height(h),
+        ..Default::default()
+    };
+    let sprites = SpriteLoader::new(1.0)
+        .render_options(height(10))
+        .dir_render_options("recursive", height(20))
+        .load_map(&paths, "tests/fixtures/svgs")
+        .unwrap();
+    assert_eq!(sprites["bicycle"].pixmap().height(), 10);
+    assert_eq!(sprites["recursive/bear"].pixmap().height(), 20);
+}
+
+#[test]
+fn loader_returns_error_for_invalid_svg() {
+    let paths = [
+        Path::new("tests/fixtures/svgs/bicycle.svg"),
+        Path::new("tests/fixtures/pngs/iceland_flag.png"),
+    ];
+    let results = SpriteLoader::new(1.0).load(&paths, "tests/fixtures");
+    assert!(results[0].is_ok());
+    let error = results[1].as_ref().err().unwrap();
+    assert_eq!(error.path, paths[1]);
+    assert_matches!(error.error, SpreetError::SvgError(_));
+}
+
+#[test]
+fn loader_returns_error_with_name_of_sprite_that_cannot_be_rendered() {
+    let paths = [Path::new("tests/fixtures/svgs/recursive/bear.svg")];
+    let results = SpriteLoader::new(0.01).load(&paths, "tests/fixtures/svgs");
+    let error = &results[0].as_ref().err().unwrap().error;
+    assert_matches!(
+        error,
+        SpreetError::SpriteError { name, source }
+            if name == "recursive/bear" && matches!(**source, SpreetError::EmptyImage { .. })
+    );
+    assert_eq!(
+        error.to_string(),
+        "Could not render sprite \"recursive/bear\": Image would be empty (0x0 pixels)"
+    );
+}
+
+#[test]
+fn loader_returns_every_error() {
+    let paths = [
+        Path::new("tests/fixtures/pngs/iceland_flag.png"),
+        Path::new("tests/fixtures/svgs/bicycle.svg"),
+        Path::new("tests/fixtures/does_not_exist.svg"),
+    ];
+    let errors = SpriteLoader::new(1.0)
+        .load_map(&paths, "tests/fixtures")
+        .err()
+        .unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].path, paths[0]);
+    assert_matches!(errors[0].error, SpreetError::SvgError(_));
+    assert_eq!(errors[1].path, paths[2]);
+    assert_matches!(errors[1].error, SpreetError::IoError(_));
+}
+
+#[test]
+fn loader_renders_variants_after_each_sprite() {
+    let mut paths = get_svg_input_paths("tests/fixtures/svgs", true).unwrap();
+    paths.sort();
+    let orientations = ["90", "mirror"].map(|s| s.parse::<Orientation>().unwrap());
+    let results = SpriteLoader::new(1.0)
+        .variants("bicycle", orientations.to_vec())
+        .variants("recursive", vec![orientations[0]])
+        .load(&paths, "tests/fixtures/svgs");
+    let names = results
+        .into_iter()
+        .map(|result| result.unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "another_bicycle",
+            "bicycle",
+            "bicycle-90",
+            "bicycle-mirror",
+            "circle",
+            "recursive/bear",
+            "recursive/bear-90",
+        ]
+    );
+}
@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use assert_matches::assert_matches;
+use spreet::{load_manifest, parse_manifest, resolve_manifest, LoadError, SpreetError};
+
+fn icons(icons: &[&str]) -> Vec<String> {
+    icons.iter().map(|icon| icon.to_string()).collect()
+}
+
+#[test]
+fn manifest_can_be_plain_text() {
+    let manifest = "# Cycling icons\nbicycle\n\n  recursive/bear.svg  \n";
+    assert_eq!(
+        parse_manifest(manifest).unwrap(),
+        icons(&["bicycle", "recursive/bear.svg"])
+    );
+}
+
+#[test]
+fn manifest_can_be_json() {
+    let manifest = r#"["bicycle", "recursive/bear.svg"]"#;
+    assert_eq!(
+        parse_manifest(manifest).unwrap(),
+        icons(&["bicycle", "recursive/bear.svg"])
+    );
+    assert_matches!(
+        parse_manifest(r#"[{"name": "bicycle"}]"#),
+        Err(SpreetError::JsonError(_))
+    );
+}
+
+#[test]
+fn load_manifest_returns_error_for_missing_file() {
+    assert_matches!(
+        load_manifest("tests/fixtures/does_not_exist.txt"),
+        Err(SpreetError::IoError(_))
+    );
+}
+
+#[test]
+fn manifest_icons_can_be_names_or_paths() {
+    let manifest = icons(&[
+        "recursive/bear",
+        "circle.svg",
+        "tests/fixtures/svgs/bicycle.svg",
+        "circle",
+    ]);
+    assert_eq!(
+        resolve_manifest(&manifest, "tests/fixtures/svgs").unwrap(),
+        [
+            "tests/fixtures/svgs/recursive/bear.svg",
+            "tests/fixtures/svgs/circle.svg",
+            "tests/fixtures/svgs/bicycle.svg",
+        ]
+        .map(PathBuf::from)
+    );
+}
+
+#[test]
+fn manifest_returns_error_for_every_missing_icon() {
+    let manifest = icons(&["bicycle", "bus", "../pngs/iceland_flag.svg", "bear"]);
+    let errors = resolve_manifest(&manifest, "tests/fixtures/svgs").unwrap_err();
+    assert_eq!(errors.len(), 3);
+    assert_eq!(errors[0].path, Path::new("tests/fixtures/svgs/bus"));
+    assert_matches!(&errors[0].error, SpreetError::MissingIcon(icon) if icon == "bus");
+    assert_matches!(&errors[2].error, SpreetError::MissingIcon(icon) if icon == "bear");
+}
+
+#[test]
+fn manifest_paths_are_found_however_input_directory_is_written() {
+    let bicycle = std::fs::canonicalize("tests/fixtures/svgs/bicycle.svg").unwrap();
+    let manifest = vec![
+        "tests/fixtures/svgs/circle.svg".to_string(),
+        bicycle.to_string_lossy().into_owned(),
+        "circle".to_string(),
+    ];
+    assert_eq!(
+        resolve_manifest(&manifest, "./tests/fixtures/svgs").unwrap(),
+        [
+            "./tests/fixtures/svgs/circle.svg",
+            "./tests/fixtures/svgs/bicycle.svg",
+        ]
+        .map(PathBuf::from)
+    );
+    let outside = icons(&["tests/fixtures/pngs/iceland_flag.svg"]);
+    assert_matches!(
+        resolve_manifest(&outside, "./tests/fixtures/svgs").unwrap_err()[..],
+        [LoadError {
+            error: SpreetError::MissingIcon(_),
+            ..
+        }]
+    );
+}
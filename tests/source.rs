@@ -0,0 +1,208 @@
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "zip")]
+use assert_matches::assert_matches;
+#[cfg(feature = "zip")]
+use spreet::SpreetError;
+#[cfg(feature = "tar")]
+use spreet::{load_svg, Sprite};
+#[cfg(any(feature = "zip", feature = "tar"))]
+use spreet::{ArchiveOptions, ArchiveSource, InputFilter};
+use spreet::{DirSource, SpriteLoader, SpriteSource};
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn dir_source_returns_paths_relative_to_its_root() {
+    let source = DirSource::new("tests/fixtures/svgs");
+    let mut svg_paths = source.svg_paths(true).unwrap();
+    svg_paths.sort();
+    assert_eq!(
+        svg_paths,
+        paths(&[
+            "another_bicycle.svg",
+            "bicycle.svg",
+            "circle.svg",
+            "recursive/bear.svg"
+        ])
+    );
+    assert!(source.read(Path::new("circle.svg")).is_ok());
+}
+
+#[test]
+fn dir_source_does_not_resolve_resources_outside_its_root() {
+    let source = DirSource::new("tests/fixtures/pngs");
+    let svg = Path::new("iceland_flag.svg");
+    assert!(source.resolve(svg, "iceland_flag.png").is_some());
+    assert!(source.resolve(svg, "../svgs/circle.svg").is_none());
+    assert!(source
+        .resolve(svg, "https://example.com/flag.png")
+        .is_none());
+}
+
+#[test]
+fn loader_names_sprites_relative_to_source_root() {
+    let source = DirSource::new("tests/fixtures/svgs");
+    let mut svg_paths = source.svg_paths(true).unwrap();
+    svg_paths.sort();
+    let names = SpriteLoader::new(1.0)
+        .load_source(&source, &svg_paths)
+        .into_iter()
+        .map(|result| result.unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["another_bicycle", "bicycle", "circle", "recursive/bear"]
+    );
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn zip_source_lists_svgs() {
+    let source = ArchiveSource::open("tests/fixtures/archives/icons.zip").unwrap();
+    assert_eq!(
+        source.svg_paths(false).unwrap(),
+        paths(&["bicycle.svg", "circle.svg"])
+    );
+    assert_eq!(
+        source.svg_paths(true).unwrap(),
+        paths(&["bicycle.svg", "circle.svg", "flags/iceland_flag.svg"])
+    );
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn tar_gz_source_lists_svgs() {
+    let source = ArchiveSource::open("tests/fixtures/archives/icons.tar.gz").unwrap();
+    assert_eq!(
+        source.svg_paths(true).unwrap(),
+        paths(&["bicycle.svg", "circle.svg", "flags/iceland_flag.svg"])
+    );
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn zip_source_lists_svgs_that_pass_filter() {
+    let source = ArchiveSource::open("tests/fixtures/archives/icons.zip").unwrap();
+    let exclude = InputFilter {
+        exclude: vec!["flags".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        source.svg_paths_with_filter(true, &exclude).unwrap(),
+        paths(&["bicycle.svg", "circle.svg"])
+    );
+    let include = InputFilter {
+        include: vec!["flags/**".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        source.svg_paths_with_filter(true, &include).unwrap(),
+        paths(&["flags/iceland_flag.svg"])
+    );
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn tar_source_reads_ignore_files_in_archive() {
+    let mut builder = tar::Builder::new(Vec::new());
+    let svg = std::fs::read("tests/fixtures/svgs/circle.svg").unwrap();
+    let files: [(&str, &[u8]); 5] = [
+        (".spreetignore", b"drafts\n"),
+        ("circle.svg", &svg),
+        ("drafts/circle.svg", &svg),
+        ("flags/.spreetignore", b"old_*.svg\n"),
+        ("flags/old_circle.svg", &svg),
+    ];
+    for (path, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, data).unwrap();
+    }
+    let source = ArchiveSource::from_tar(
+        builder.into_inner().unwrap().as_slice(),
+        &ArchiveOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        source
+            .svg_paths_with_filter(true, &InputFilter::default())
+            .unwrap(),
+        paths(&["circle.svg"])
+    );
+    let without_ignore_files = InputFilter {
+        ignore_files: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        source
+            .svg_paths_with_filter(true, &without_ignore_files)
+            .unwrap(),
+        paths(&["circle.svg", "drafts/circle.svg", "flags/old_circle.svg"])
+    );
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn archive_sprites_match_sprites_from_files() {
+    let source = ArchiveSource::open("tests/fixtures/archives/icons.tar.gz").unwrap();
+    let svg_paths = source.svg_paths(true).unwrap();
+    let results = SpriteLoader::new(1.0).load_source(&source, &svg_paths);
+    let (name, sprite) = results[2].as_ref().unwrap();
+    // The flag's PNG is resolved relative to the SVG, inside the archive.
+    let tree = load_svg("tests/fixtures/pngs/iceland_flag.svg").unwrap();
+    let expected = Sprite::new(tree, 1.0).unwrap();
+    assert_eq!(name, "flags/iceland_flag");
+    assert_eq!(sprite.pixmap(), expected.pixmap());
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn archive_source_returns_error_for_invalid_archive() {
+    assert_matches!(
+        ArchiveSource::open("tests/fixtures/svgs/bicycle.svg"),
+        Err(SpreetError::IoError(_))
+    );
+    assert_matches!(
+        ArchiveSource::from_zip(
+            std::fs::File::open("tests/fixtures/svgs/bicycle.svg").unwrap(),
+            &ArchiveOptions::default(),
+        ),
+        Err(SpreetError::ZipError(_))
+    );
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn archive_source_only_reads_images() {
+    let source = ArchiveSource::open("tests/fixtures/archives/icons.zip").unwrap();
+    assert!(source.read(Path::new("flags/iceland_flag.png")).is_ok());
+    assert_matches!(
+        source.read(Path::new("README.txt")),
+        Err(SpreetError::IoError(_))
+    );
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn archive_source_returns_error_for_file_over_size_limit() {
+    // The largest image in the archive is `bicycle.svg`, at 1104 bytes.
+    let options = ArchiveOptions {
+        max_file_size: 1000,
+    };
+    assert_matches!(
+        ArchiveSource::open_with_options("tests/fixtures/archives/icons.zip", &options),
+        Err(SpreetError::ArchiveFileTooLarge { path, max_size: 1000 })
+            if path == Path::new("bicycle.svg")
+    );
+    let options = ArchiveOptions {
+        max_file_size: 1104,
+    };
+    assert!(
+        ArchiveSource::open_with_options("tests/fixtures/archives/icons.zip", &options).is_ok()
+    );
+}
@@ -1,8 +1,14 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
 use assert_matches::assert_matches;
-use resvg::usvg::{Options, Rect, Tree};
-use spreet::{load_svg, sprite_name, SpreetError, Sprite};
+use resvg::usvg::{Options, Rect, ShapeRendering, Tree};
+#[cfg(any(feature = "webp", feature = "avif"))]
+use spreet::ImageFormat;
+use spreet::{
+    load_svg, load_svg_with_options, sprite_name, IndexFormat, LoadOptions, Orientation, PngFilter,
+    PngOptions, RenderOptions, SpreetError, Sprite, Spritesheet, TargetSize,
+};
 
 #[test]
 fn sprite_name_works_with_root_files() {
@@ -82,7 +88,7 @@ fn sprite_name_returns_error_when_base_path_not_parent_of_path() {
 fn unstretchable_icon_has_no_metadata() {
     let path = Path::new("./tests/fixtures/svgs/bicycle.svg");
     let tree = load_svg(path).unwrap();
-    let sprite = Sprite::new(tree, 1).unwrap();
+    let sprite = Sprite::new(tree, 1.0).unwrap();
 
     assert!(sprite.content_area().is_none());
     assert!(sprite.stretch_x_areas().is_none());
@@ -93,7 +99,7 @@ fn unstretchable_icon_has_no_metadata() {
 fn stretchable_icon_has_metadata() {
     let path = Path::new("./tests/fixtures/stretchable/cn-nths-expy-2-affinity.svg");
     let tree = load_svg(path).unwrap();
-    let sprite = Sprite::new(tree, 1).unwrap();
+    let sprite = Sprite::new(tree, 1.0).unwrap();
 
     assert_eq!(
         sprite.content_area().unwrap(),
@@ -113,7 +119,7 @@ fn stretchable_icon_has_metadata() {
 fn stretchable_icons_can_use_stretch_shorthand() {
     let path = Path::new("./tests/fixtures/stretchable/cn-nths-expy-2-inkscape-plain.svg");
     let tree = load_svg(path).unwrap();
-    let sprite = Sprite::new(tree, 1).unwrap();
+    let sprite = Sprite::new(tree, 1.0).unwrap();
 
     assert!(sprite.content_area().is_none());
     assert_eq!(
@@ -130,7 +136,7 @@ fn stretchable_icons_can_use_stretch_shorthand() {
 fn stretchable_icon_can_have_multiple_horizontal_stretch_zones() {
     let path = Path::new("./tests/fixtures/stretchable/ae-national-3-affinity.svg");
     let tree = load_svg(path).unwrap();
-    let sprite = Sprite::new(tree, 1).unwrap();
+    let sprite = Sprite::new(tree, 1.0).unwrap();
 
     assert_eq!(
         sprite.stretch_x_areas().unwrap(),
@@ -145,7 +151,7 @@ fn stretchable_icon_can_have_multiple_horizontal_stretch_zones() {
 fn stretchable_icon_metadata_matches_pixel_ratio() {
     let path = Path::new("./tests/fixtures/stretchable/cn-nths-expy-2-affinity.svg");
     let tree = load_svg(path).unwrap();
-    let sprite = Sprite::new(tree, 2).unwrap();
+    let sprite = Sprite::new(tree, 2.0).unwrap();
 
     assert_eq!(
         sprite.content_area().unwrap(),
@@ -165,7 +171,7 @@ fn stretchable_icon_metadata_matches_pixel_ratio() {
 fn stretchable_icon_with_empty_metadata_is_ignored() {
     let svg = "<svg xmlns='http://www.w3.org/2000/svg'><path id='mapbox-content'/></svg>";
     let tree = Tree::from_str(svg, &Options::default()).unwrap();
-    let sprite = Sprite::new(tree, 1).unwrap();
+    let sprite = Sprite::new(tree, 1.0).unwrap();
 
     assert!(sprite.content_area().is_none());
 }
@@ -174,7 +180,7 @@ fn stretchable_icon_with_empty_metadata_is_ignored() {
 fn stretchable_icon_with_invalid_metadata_is_ignored() {
     let svg = "<svg xmlns='http://www.w3.org/2000/svg'><path id='mapbox-content' d='foo'/></svg>";
     let tree = Tree::from_str(svg, &Options::default()).unwrap();
-    let sprite = Sprite::new(tree, 1).unwrap();
+    let sprite = Sprite::new(tree, 1.0).unwrap();
 
     assert!(sprite.content_area().is_none());
 }
@@ -187,7 +193,590 @@ fn stretchable_icon_with_metadata_in_hidden_element_is_ignored() {
     </svg>
     ";
     let tree = Tree::from_str(svg, &Options::default()).unwrap();
-    let sprite = Sprite::new(tree, 1).unwrap();
+    let sprite = Sprite::new(tree, 1.0).unwrap();
 
     assert!(sprite.content_area().is_none());
 }
+
+#[test]
+fn sprite_can_be_rendered_at_target_height() {
+    let path = Path::new("./tests/fixtures/svgs/bicycle.svg");
+    let options = RenderOptions {
+        size: TargetSize::Height(30),
+        ..Default::default()
+    };
+    let sprite = Sprite::new_with_options(load_svg(path).unwrap(), 2.0, &options).unwrap();
+
+    assert_eq!(sprite.pixmap().width(), 60);
+    assert_eq!(sprite.pixmap().height(), 60);
+}
+
+#[test]
+fn sprite_rendered_in_box_preserves_aspect_ratio() {
+    let svg = "<svg xmlns='http://www.w3.org/2000/svg' width='40' height='20'/>";
+    let tree = Tree::from_str(svg, &Options::default()).unwrap();
+    let options = RenderOptions {
+        size: TargetSize::Box {
+            width: 20,
+            height: 20,
+        },
+        ..Default::default()
+    };
+    let sprite = Sprite::new_with_options(tree, 1.0, &options).unwrap();
+
+    assert_eq!(sprite.pixmap().width(), 20);
+    assert_eq!(sprite.pixmap().height(), 10);
+}
+
+#[test]
+fn sprite_can_be_padded_to_a_square() {
+    let svg = "<svg xmlns='http://www.w3.org/2000/svg' width='40' height='20'/>";
+    let tree = Tree::from_str(svg, &Options::default()).unwrap();
+    let options = RenderOptions {
+        square: true,
+        ..Default::default()
+    };
+    let sprite = Sprite::new_with_options(tree, 1.0, &options).unwrap();
+
+    assert_eq!(sprite.pixmap().width(), 40);
+    assert_eq!(sprite.pixmap().height(), 40);
+}
+
+#[test]
+fn stretchable_icon_metadata_matches_target_size() {
+    let path = Path::new("./tests/fixtures/stretchable/cn-nths-expy-2-affinity.svg");
+    let options = RenderOptions {
+        size: TargetSize::Width(40),
+        square: true,
+        ..Default::default()
+    };
+    let sprite = Sprite::new_with_options(load_svg(path).unwrap(), 1.0, &options).unwrap();
+
+    // The 40x46 image is centred in a 46x46 square, so it's offset 3px from the left.
+    assert_eq!(
+        sprite.content_area().unwrap(),
+        Rect::from_ltrb(7.0, 10.0, 39.0, 36.0).unwrap()
+    );
+}
+
+#[test]
+fn stretchable_icon_metadata_follows_quarter_turn() {
+    let path = Path::new("./tests/fixtures/stretchable/cn-nths-expy-2-affinity.svg");
+    let options = RenderOptions {
+        orientation: Orientation {
+            rotation: 90.0,
+            mirror: false,
+        },
+        ..Default::default()
+    };
+    let sprite = Sprite::new_with_options(load_svg(path).unwrap(), 1.0, &options).unwrap();
+
+    // The 20x23 image is turned clockwise, so its vertical stretch zone becomes horizontal.
+    assert_eq!(sprite.pixmap().width(), 23);
+    assert_eq!(sprite.pixmap().height(), 20);
+    assert_eq!(
+        sprite.content_area().unwrap(),
+        Rect::from_ltrb(5.0, 2.0, 18.0, 18.0).unwrap()
+    );
+    assert_eq!(
+        sprite.stretch_x_areas().unwrap(),
+        [Rect::from_ltrb(7.0, 0.0, 18.0, 0.0).unwrap()]
+    );
+    assert_eq!(
+        sprite.stretch_y_areas().unwrap(),
+        [Rect::from_ltrb(23.0, 4.0, 23.0, 16.0).unwrap()]
+    );
+}
+
+#[test]
+fn mirrored_sprite_has_mirrored_metadata() {
+    let path = Path::new("./tests/fixtures/stretchable/cn-nths-expy-2-affinity.svg");
+    let options = RenderOptions {
+        orientation: "mirror".parse().unwrap(),
+        ..Default::default()
+    };
+    let sprite = Sprite::new_with_options(load_svg(path).unwrap(), 1.0, &options).unwrap();
+
+    assert_eq!(
+        sprite.content_area().unwrap(),
+        Rect::from_ltrb(2.0, 5.0, 18.0, 18.0).unwrap()
+    );
+    assert_eq!(
+        sprite.stretch_x_areas().unwrap(),
+        [Rect::from_ltrb(4.0, 0.0, 16.0, 0.0).unwrap()]
+    );
+}
+
+#[test]
+fn sprite_rotated_by_any_angle_fits_its_bitmap() {
+    let svg = "<svg xmlns='http://www.w3.org/2000/svg' width='40' height='20'>\
+               <rect id='mapbox-content' width='40' height='20' fill='red'/></svg>";
+    let tree = Tree::from_str(svg, &Options::default()).unwrap();
+    let options = RenderOptions {
+        orientation: "45".parse().unwrap(),
+        ..Default::default()
+    };
+    let sprite = Sprite::new_with_options(tree, 1.0, &options).unwrap();
+
+    // (40 + 20) * sin 45° = 42.4
+    assert_eq!(sprite.pixmap().width(), 43);
+    assert_eq!(sprite.pixmap().height(), 43);
+    assert_eq!(sprite.content_area(), None);
+}
+
+#[test]
+fn orientation_can_be_parsed_and_formatted() {
+    for s in ["90", "180", "270", "45", "mirror", "mirror-90"] {
+        assert_eq!(s.parse::<Orientation>().unwrap().to_string(), s);
+    }
+    assert_eq!(
+        "-90".parse::<Orientation>().unwrap(),
+        "270".parse::<Orientation>().unwrap()
+    );
+    assert!("sideways".parse::<Orientation>().is_err());
+    assert!("mirror90".parse::<Orientation>().is_err());
+}
+
+#[test]
+fn sprite_can_be_rendered_at_fractional_pixel_ratio() {
+    let path = Path::new("./tests/fixtures/stretchable/cn-nths-expy-2-affinity.svg");
+    let sprite = Sprite::new(load_svg(path).unwrap(), 1.5).unwrap();
+
+    assert_eq!(sprite.pixmap().width(), 30);
+    assert_eq!(sprite.pixmap().height(), 35);
+    assert_eq!(
+        sprite.content_area().unwrap(),
+        Rect::from_ltrb(3.0, 7.5, 27.0, 27.0).unwrap()
+    );
+}
+
+#[test]
+fn sprite_can_be_snapped_to_pixels() {
+    let svg = "
+    <svg xmlns='http://www.w3.org/2000/svg' width='20' height='20' viewBox='0 0 15 15'>
+        <rect width='15' height='15'/>
+    </svg>
+    ";
+    let tree = Tree::from_str(svg, &Options::default()).unwrap();
+    let options = RenderOptions {
+        snap_to_pixels: true,
+        ..Default::default()
+    };
+    let sprite = Sprite::new_with_options(tree, 1.0, &options).unwrap();
+    let alpha = |x, y| sprite.pixmap().pixel(x, y).unwrap().alpha();
+
+    // One SVG unit is rendered as one pixel, and the 15px square is centred in the 20px bitmap.
+    assert_eq!(sprite.pixmap().width(), 20);
+    assert_eq!(alpha(2, 2), 0);
+    assert_eq!(alpha(3, 3), 255);
+    assert_eq!(alpha(17, 17), 255);
+    assert_eq!(alpha(18, 18), 0);
+}
+
+#[test]
+fn sprite_can_be_rendered_without_antialiasing() {
+    let path = Path::new("./tests/fixtures/svgs/circle.svg");
+    let options = LoadOptions {
+        shape_rendering: ShapeRendering::CrispEdges,
+        ..Default::default()
+    };
+    let tree = load_svg_with_options(path, &options).unwrap();
+    let sprite = Sprite::new(tree, 1.0).unwrap();
+
+    assert!(sprite
+        .pixmap()
+        .pixels()
+        .iter()
+        .all(|pixel| pixel.alpha() == 0 || pixel.alpha() == 255));
+}
+
+/// Returns `true` if any pixel in the sprite is pure red, the colour of the text in the text
+/// fixtures.
+fn has_red_text(sprite: &Sprite) -> bool {
+    sprite
+        .pixmap()
+        .pixels()
+        .iter()
+        .any(|pixel| pixel.red() > 0 && pixel.green() == 0 && pixel.blue() == 0)
+}
+
+#[test]
+fn sprite_text_is_not_rendered_without_fonts() {
+    let path = Path::new("./tests/fixtures/text/minimal.svg");
+    let options = LoadOptions {
+        system_fonts: false,
+        ..Default::default()
+    };
+    let tree = load_svg_with_options(path, &options).unwrap();
+    let sprite = Sprite::new(tree, 1.0).unwrap();
+
+    assert!(!has_red_text(&sprite));
+}
+
+#[test]
+fn sprite_text_can_be_rendered_with_font_files() {
+    let path = Path::new("./tests/fixtures/text/minimal.svg");
+    // The SVG asks for DejaVu Sans, so Tuffy is only used once it's the default font family.
+    let options = LoadOptions {
+        fonts: vec!["tests/fixtures/fonts".into()],
+        system_fonts: false,
+        ..Default::default()
+    };
+    let tree = load_svg_with_options(path, &options).unwrap();
+    assert!(!has_red_text(&Sprite::new(tree, 1.0).unwrap()));
+
+    let options = LoadOptions {
+        font_family: Some(String::from("Tuffy")),
+        ..options
+    };
+    let tree = load_svg_with_options(path, &options).unwrap();
+    assert!(has_red_text(&Sprite::new(tree, 1.0).unwrap()));
+}
+
+#[test]
+fn load_svg_returns_error_for_missing_font_file() {
+    let path = Path::new("./tests/fixtures/text/minimal.svg");
+    let options = LoadOptions {
+        fonts: vec!["tests/fixtures/fonts/missing.ttf".into()],
+        ..Default::default()
+    };
+    assert_matches!(
+        load_svg_with_options(path, &options),
+        Err(SpreetError::IoError(_))
+    );
+}
+
+fn gradient_spritesheet(max_error: f32) -> Spritesheet {
+    let svg = "
+    <svg xmlns='http://www.w3.org/2000/svg' width='64' height='64'>
+        <linearGradient id='a' x2='1' y2='1'>
+            <stop offset='0' stop-color='#f00'/>
+            <stop offset='1' stop-color='#00f' stop-opacity='0.5'/>
+        </linearGradient>
+        <circle cx='32' cy='32' r='30' fill='url(#a)'/>
+    </svg>
+    ";
+    let tree = Tree::from_str(svg, &Options::default()).unwrap();
+    let sprites = BTreeMap::from([("gradient".to_string(), Sprite::new(tree, 2.0).unwrap())]);
+    Spritesheet::build()
+        .sprites(sprites)
+        .quantize(max_error)
+        .generate()
+        .unwrap()
+}
+
+#[test]
+fn spritesheet_can_be_quantized() {
+    let (png, report) = gradient_spritesheet(1.0).encode_png_with_report().unwrap();
+    assert_eq!(png.len(), report.size);
+    let report = report.quantization.unwrap();
+
+    assert!(report.applied());
+    assert!(report.colors <= 256);
+    assert!(report.error <= 1.0);
+    assert_eq!(png.len(), report.indexed_size.unwrap());
+    let decoder = png::Decoder::new(png.as_slice());
+    assert_eq!(
+        decoder.read_info().unwrap().info().color_type,
+        png::ColorType::Indexed
+    );
+}
+
+#[test]
+fn spritesheet_is_not_quantized_when_error_is_too_large() {
+    let (png, report) = gradient_spritesheet(0.0).encode_png_with_report().unwrap();
+    let report = report.quantization.unwrap();
+
+    assert!(!report.applied());
+    assert!(report.indexed_size.is_none());
+    assert_eq!(png.len(), report.rgba_size);
+}
+
+#[cfg(feature = "webp")]
+#[test]
+fn spritesheet_can_be_encoded_as_webp() {
+    let spritesheet = Spritesheet::build()
+        .sprites(BTreeMap::from([(
+            "bicycle".to_string(),
+            Sprite::new(load_svg("./tests/fixtures/svgs/bicycle.svg").unwrap(), 1.0).unwrap(),
+        )]))
+        .format(ImageFormat::LosslessWebp)
+        .generate()
+        .unwrap();
+    let webp = spritesheet.encode().unwrap();
+
+    assert_eq!(spritesheet.format().extension(), "webp");
+    assert_eq!(&webp[..4], b"RIFF");
+    assert_eq!(&webp[8..12], b"WEBP");
+}
+
+#[cfg(feature = "webp")]
+#[test]
+fn spritesheet_cannot_be_quantized_unless_png() {
+    let result = Spritesheet::build()
+        .sprites(BTreeMap::from([(
+            "bicycle".to_string(),
+            Sprite::new(load_svg("./tests/fixtures/svgs/bicycle.svg").unwrap(), 1.0).unwrap(),
+        )]))
+        .quantize(2.0)
+        .format(ImageFormat::LosslessWebp)
+        .generate();
+    assert_matches!(result.err(), Some(SpreetError::QuantizeUnsupported));
+}
+
+#[cfg(feature = "avif")]
+#[test]
+fn spritesheet_can_be_encoded_as_avif() {
+    let spritesheet = Spritesheet::build()
+        .sprites(BTreeMap::from([(
+            "bicycle".to_string(),
+            Sprite::new(load_svg("./tests/fixtures/svgs/bicycle.svg").unwrap(), 1.0).unwrap(),
+        )]))
+        .format(ImageFormat::Avif { quality: 80.0 })
+        .generate()
+        .unwrap();
+    let avif = spritesheet.encode().unwrap();
+
+    assert_eq!(spritesheet.format().extension(), "avif");
+    assert_eq!(&avif[4..12], b"ftypavif");
+}
+
+#[test]
+fn spritesheet_png_optimisation_can_be_configured() {
+    let encode = |png_options| {
+        Spritesheet::build()
+            .sprites(BTreeMap::from([(
+                "bicycle".to_string(),
+                Sprite::new(load_svg("./tests/fixtures/svgs/bicycle.svg").unwrap(), 1.0).unwrap(),
+            )]))
+            .png_options(png_options)
+            .generate()
+            .unwrap()
+            .encode_png()
+            .unwrap()
+    };
+    let fast = encode(PngOptions::fast());
+    let default = encode(PngOptions::default());
+    let max = encode(PngOptions {
+        filters: vec![PngFilter::None, PngFilter::Paeth],
+        ..PngOptions::max()
+    });
+
+    assert!(fast.len() > default.len());
+    assert!(max.len() <= default.len());
+}
+
+fn square_sprite(fill: &str) -> Sprite {
+    let svg = format!(
+        "<svg xmlns='http://www.w3.org/2000/svg' width='16' height='16'>
+            <rect x='2' y='2' width='12' height='12' fill='{fill}'/>
+        </svg>"
+    );
+    Sprite::new(Tree::from_str(&svg, &Options::default()).unwrap(), 1.0).unwrap()
+}
+
+#[test]
+fn make_unique_removes_identical_sprites() {
+    let sprites = BTreeMap::from([
+        ("b".to_string(), square_sprite("#000")),
+        ("a".to_string(), square_sprite("#000")),
+        ("c".to_string(), square_sprite("#020202")),
+    ]);
+    let spritesheet = Spritesheet::build()
+        .sprites(sprites)
+        .make_unique()
+        .generate()
+        .unwrap();
+    let index = spritesheet.get_index();
+
+    assert_eq!(index.len(), 3);
+    assert_eq!(index["a"].x, index["b"].x);
+    assert_ne!((index["a"].x, index["a"].y), (index["c"].x, index["c"].y));
+    assert_eq!(spritesheet.duplicate_groups(), [vec!["a", "b"]]);
+    assert!(spritesheet.near_duplicates().is_empty());
+}
+
+#[test]
+fn make_unique_with_tolerance_removes_near_duplicates() {
+    let sprites = BTreeMap::from([
+        ("a".to_string(), square_sprite("#000")),
+        ("b".to_string(), square_sprite("#020202")),
+        ("c".to_string(), square_sprite("#f00")),
+    ]);
+    let spritesheet = Spritesheet::build()
+        .sprites(sprites)
+        .make_unique_with_tolerance(2.0)
+        .generate()
+        .unwrap();
+    let index = spritesheet.get_index();
+
+    assert_eq!((index["a"].x, index["a"].y), (index["b"].x, index["b"].y));
+    assert_ne!((index["a"].x, index["a"].y), (index["c"].x, index["c"].y));
+    assert_eq!(spritesheet.near_duplicates().len(), 1);
+    let near_duplicate = &spritesheet.near_duplicates()[0];
+    assert_eq!(near_duplicate.name, "b");
+    assert_eq!(near_duplicate.duplicate_of, "a");
+    assert!(near_duplicate.error > 0.0 && near_duplicate.error <= 2.0);
+}
+
+#[test]
+fn spritesheet_reports_packing_efficiency_and_png_sizes() {
+    let spritesheet = Spritesheet::build()
+        .sprites(BTreeMap::from([
+            ("a".to_string(), square_sprite("#000")),
+            ("b".to_string(), square_sprite("#f00")),
+        ]))
+        .generate()
+        .unwrap();
+    let (png, report) = spritesheet.encode_png_with_report().unwrap();
+
+    assert_eq!(spritesheet.sprite_area(), 2 * 16 * 16);
+    assert_eq!(
+        spritesheet.packing_efficiency(),
+        512.0 / (spritesheet.width() * spritesheet.height()) as f32
+    );
+    assert_eq!(report.size, png.len());
+    assert!(report.unoptimized_size > report.size);
+    assert!(report.quantization.is_none());
+}
+
+#[test]
+fn sprite_returns_error_when_image_is_empty() {
+    let tree = load_svg("./tests/fixtures/svgs/circle.svg").unwrap();
+    assert_matches!(
+        Sprite::new(tree, 0.01).err(),
+        Some(SpreetError::EmptyImage {
+            width: 0,
+            height: 0
+        })
+    );
+}
+
+#[test]
+fn spritesheet_returns_error_with_largest_sprite_when_packing_fails() {
+    let square = |size: u32| {
+        let svg = format!(
+            "<svg xmlns='http://www.w3.org/2000/svg' width='{size}' height='{size}'>\
+             <rect width='{size}' height='{size}'/></svg>"
+        );
+        Sprite::new(Tree::from_str(&svg, &Options::default()).unwrap(), 1.0).unwrap()
+    };
+    // The spacing makes the tiny sprites too big to pack into ten times their area.
+    let error = Spritesheet::build()
+        .sprites(BTreeMap::from([
+            ("big".to_string(), square(2)),
+            ("dot".to_string(), square(1)),
+        ]))
+        .spacing(30)
+        .generate()
+        .err()
+        .unwrap();
+    assert_matches!(&error, SpreetError::PackingFailed { largest, .. } if largest == "big");
+    assert!(error.to_string().contains("the largest is \"big\""));
+}
+
+#[test]
+fn sprite_returns_error_when_image_is_too_large() {
+    let tree = load_svg("./tests/fixtures/svgs/circle.svg").unwrap();
+    assert_matches!(
+        Sprite::new(tree, 1e9).err(),
+        Some(SpreetError::TooLarge { .. })
+    );
+}
+
+#[test]
+fn spritesheet_returns_error_when_there_are_no_sprites() {
+    assert_matches!(
+        Spritesheet::build()
+            .sprites(BTreeMap::new())
+            .generate()
+            .err(),
+        Some(SpreetError::EmptyImage { .. })
+    );
+}
+
+fn index_spritesheet() -> Spritesheet {
+    Spritesheet::build()
+        .sprites(BTreeMap::from([
+            ("a".to_string(), square_sprite("#000")),
+            ("b & c".to_string(), square_sprite("#f00")),
+        ]))
+        .generate()
+        .unwrap()
+}
+
+#[test]
+fn spritesheet_index_can_be_encoded_as_deck_gl_icon_mapping() {
+    let spritesheet = index_spritesheet();
+    let index = spritesheet.get_index();
+    let json = spritesheet
+        .encode_index(IndexFormat::DeckGl, "sprite.png", true)
+        .unwrap();
+    let mapping: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        mapping["b & c"],
+        serde_json::json!({
+            "x": index["b & c"].x,
+            "y": index["b & c"].y,
+            "width": 16,
+            "height": 16,
+            "mask": false
+        })
+    );
+}
+
+#[test]
+fn spritesheet_index_can_be_encoded_as_texture_packer_json() {
+    let spritesheet = index_spritesheet();
+    let index = spritesheet.get_index();
+    let hash: serde_json::Value = serde_json::from_str(
+        &spritesheet
+            .encode_index(IndexFormat::TexturePackerHash, "sprite.png", false)
+            .unwrap(),
+    )
+    .unwrap();
+    let array: serde_json::Value = serde_json::from_str(
+        &spritesheet
+            .encode_index(IndexFormat::TexturePackerArray, "sprite.png", false)
+            .unwrap(),
+    )
+    .unwrap();
+
+    let frame = serde_json::json!({"x": index["a"].x, "y": index["a"].y, "w": 16, "h": 16});
+    assert_eq!(hash["frames"]["a"]["frame"], frame);
+    assert_eq!(
+        hash["frames"]["a"]["sourceSize"],
+        serde_json::json!({"w": 16, "h": 16})
+    );
+    assert_eq!(array["frames"][0]["filename"], "a");
+    assert_eq!(array["frames"][0]["frame"], frame);
+    assert_eq!(array["frames"][1]["filename"], "b & c");
+    for index in [hash, array] {
+        assert_eq!(index["meta"]["image"], "sprite.png");
+        assert_eq!(index["meta"]["scale"], "1");
+        assert_eq!(
+            index["meta"]["size"],
+            serde_json::json!({"w": spritesheet.width(), "h": spritesheet.height()})
+        );
+    }
+}
+
+#[test]
+fn spritesheet_index_can_be_encoded_as_xml_atlas() {
+    let spritesheet = index_spritesheet();
+    let index = spritesheet.get_index();
+    let xml = spritesheet
+        .encode_index(IndexFormat::Xml, "sprite.png", false)
+        .unwrap();
+    assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+    assert!(xml.contains(&format!(
+        "<TextureAtlas imagePath=\"sprite.png\" width=\"{}\" height=\"{}\">",
+        spritesheet.width(),
+        spritesheet.height()
+    )));
+    assert!(xml.contains(&format!(
+        "  <SubTexture name=\"b &amp; c\" x=\"{}\" y=\"{}\" width=\"16\" height=\"16\"/>\n",
+        index["b & c"].x, index["b & c"].y
+    )));
+    assert!(xml.ends_with("</TextureAtlas>\n"));
+}
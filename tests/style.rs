@@ -0,0 +1,99 @@
+use assert_matches::assert_matches;
+use serde_json::json;
+use spreet::{SpreetError, StyleIcons};
+
+fn names(icons: &StyleIcons) -> Vec<&str> {
+    icons.names().iter().map(String::as_str).collect()
+}
+
+#[test]
+fn style_icons_are_collected_from_icon_and_pattern_properties() {
+    let style = json!({
+        "layers": [
+            {"id": "water", "paint": {"fill-pattern": "waves", "fill-color": "blue"}},
+            {"id": "rail", "paint": {"line-pattern": "rail"}},
+            {"id": "poi", "layout": {"icon-image": "bus", "text-field": "{name}"}}
+        ]
+    });
+    let icons = StyleIcons::from_style(&style);
+    assert_eq!(names(&icons), ["bus", "rail", "waves"]);
+    assert!(icons.dynamic_layers().is_empty());
+}
+
+#[test]
+fn style_icons_are_collected_from_expression_branches() {
+    let style = json!({
+        "layers": [
+            {"id": "match", "layout": {"icon-image": [
+                "match", ["get", "class"], "zoo", "zoo", ["park", "garden"], "park", "marker"
+            ]}},
+            {"id": "case", "layout": {"icon-image": [
+                "case", ["has", "ref"], ["image", "shield"], ["literal", "sign"]
+            ]}},
+            {"id": "step", "layout": {"icon-image": ["step", ["zoom"], "dot", 14, "pin"]}},
+            {"id": "coalesce", "layout": {"icon-image": [
+                "coalesce", ["concat", "shop", "-", "15"], "shop"
+            ]}},
+            {"id": "stops", "layout": {"icon-image": {"stops": [[10, "town"], [14, "city"]]}}},
+            {"id": "text", "layout": {"text-field": [
+                "format", ["image", "flag"], {}, ["get", "name"], {}
+            ]}}
+        ]
+    });
+    let icons = StyleIcons::from_style(&style);
+    assert_eq!(
+        names(&icons),
+        [
+            "city", "dot", "flag", "marker", "park", "pin", "shield", "shop", "shop-15", "sign",
+            "town", "zoo"
+        ]
+    );
+    assert!(icons.dynamic_layers().is_empty());
+}
+
+#[test]
+fn style_icons_report_layers_with_names_from_feature_data() {
+    let style = json!({
+        "layers": [
+            {"id": "get", "layout": {"icon-image": ["get", "icon"]}},
+            {"id": "token", "layout": {"icon-image": "{class}-15"}},
+            {"id": "concat", "layout": {"icon-image": [
+                "case", ["has", "ref"], ["concat", "shield-", ["get", "ref"]], "sign"
+            ]}},
+            {"id": "identity", "layout": {"icon-image": {
+                "type": "identity", "property": "icon", "default": "marker"
+            }}},
+            {"id": "property", "layout": {"icon-image": {"property": "icon"}}},
+            {"id": "static", "layout": {"icon-image": "bus"}}
+        ]
+    });
+    let icons = StyleIcons::from_style(&style);
+    assert_eq!(names(&icons), ["bus", "marker", "sign"]);
+    assert_eq!(
+        icons.dynamic_layers().iter().collect::<Vec<_>>(),
+        ["concat", "get", "identity", "property", "token"]
+    );
+}
+
+#[test]
+fn style_icons_can_allow_names_matching_globs() {
+    let style = json!({"layers": [{"id": "poi", "layout": {"icon-image": "bus"}}]});
+    let icons = StyleIcons::from_style(&style)
+        .allow(&["shield-*", "flags/**"])
+        .unwrap();
+    assert!(icons.contains("bus"));
+    assert!(icons.contains("shield-a1"));
+    assert!(icons.contains("flags/iceland"));
+    assert!(!icons.contains("tram"));
+    assert_eq!(names(&icons), ["bus"]);
+}
+
+#[test]
+fn style_icons_can_be_loaded_from_file() {
+    let icons = StyleIcons::load("tests/fixtures/styles/style.json").unwrap();
+    assert_eq!(names(&icons), ["circle", "recursive/bear", "train"]);
+    assert_matches!(
+        StyleIcons::load("tests/fixtures/svgs/circle.svg"),
+        Err(SpreetError::JsonError(_))
+    );
+}
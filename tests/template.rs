@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use assert_matches::assert_matches;
+use spreet::{
+    load_template_sets, LoadOptions, SpreetError, Sprite, SpriteLoader, SpriteTemplate,
+    TemplateValues,
+};
+
+fn tuffy() -> LoadOptions {
+    LoadOptions {
+        fonts: vec!["tests/fixtures/fonts".into()],
+        system_fonts: false,
+        ..Default::default()
+    }
+}
+
+fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn template_fills_placeholders() {
+    let template = SpriteTemplate::new(
+        "<svg xmlns='http://www.w3.org/2000/svg' width='8' height='8'>\
+         <style>rect{stroke:none}</style><rect width='8' height='8' fill='{color}'/>\
+         <desc>{label}</desc></svg>",
+        "template.svg",
+    );
+    let svg = template
+        .render_svg(
+            &values(&[("color", "#f00"), ("label", "<A & B>")]),
+            &tuffy(),
+        )
+        .unwrap();
+    assert_eq!(
+        svg,
+        "<svg xmlns='http://www.w3.org/2000/svg' width='8' height='8'>\
+         <style>rect{stroke:none}</style><rect width='8' height='8' fill='#f00'/>\
+         <desc>&lt;A &amp; B&gt;</desc></svg>"
+    );
+}
+
+#[test]
+fn template_returns_error_for_missing_value() {
+    let template = SpriteTemplate::from_file("tests/fixtures/templates/shield.svg").unwrap();
+    assert_matches!(
+        template.render_svg(&values(&[("ref", "1")]), &tuffy()),
+        Err(SpreetError::MissingTemplateValue(name)) if name == "color"
+    );
+}
+
+#[test]
+fn template_grows_with_text() {
+    let template = SpriteTemplate::from_file("tests/fixtures/templates/shield.svg").unwrap();
+    let short = template
+        .render_tree(&values(&[("ref", "1"), ("color", "#00f")]), &tuffy())
+        .unwrap();
+    let long = template
+        .render_tree(&values(&[("ref", "1000"), ("color", "#00f")]), &tuffy())
+        .unwrap();
+    assert_eq!(short.size().width(), 20.0);
+    assert_eq!(long.size().width(), 33.0);
+    assert_eq!(long.size().height(), 20.0);
+
+    let sprite = Sprite::new(long, 1.0).unwrap();
+    let content = sprite.content_area().unwrap();
+    assert_eq!((content.left(), content.right()), (3.0, 30.0));
+    let stretch = sprite.stretch_x_areas().unwrap();
+    assert_eq!((stretch[0].left(), stretch[0].right()), (4.0, 29.0));
+}
+
+#[test]
+fn template_sets_are_loaded_relative_to_their_file() {
+    let sets = load_template_sets("tests/fixtures/templates/shields.json").unwrap();
+    assert_eq!(sets.len(), 2);
+    assert_eq!(
+        sets[0].template,
+        Path::new("tests/fixtures/templates/shield.svg")
+    );
+    assert_eq!(sets[0].values[0], TemplateValues::Ref(String::from("1")));
+    assert_eq!(
+        sets[0].value_maps()[0],
+        values(&[("color", "#00f"), ("ref", "1")])
+    );
+}
+
+#[test]
+fn loader_generates_sprites_from_templates() {
+    let sets = load_template_sets("tests/fixtures/templates/shields.json").unwrap();
+    let results = SpriteLoader::new(2.0)
+        .load_options(tuffy())
+        .load_templates(&sets);
+    let sprites = results
+        .into_iter()
+        .map(|result| result.unwrap())
+        .map(|(name, sprite)| (name, sprite.pixmap().width()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sprites,
+        vec![
+            (String::from("shield-blue-1"), 40),
+            (String::from("shield-blue-95"), 40),
+            (String::from("shield-blue-1000"), 66),
+            (String::from("shield-red-7"), 40),
+        ]
+    );
+}